@@ -0,0 +1,57 @@
+use dioxus::prelude::*;
+use schemas::scope_dictionary_css;
+
+//the stylesheet of a dictionary followed by the user's overrides for it, both scoped
+//to that dictionary's entries. render it once for each dictionary that has entries on screen
+#[component]
+pub fn DictionaryStyles(dictionary: String, styles: Option<String>, user_styles: Option<String>) -> Element {
+    let css = [styles, user_styles]
+        .into_iter()
+        .flatten()
+        .map(|css| scope_dictionary_css(&css, &dictionary))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    rsx! {
+        if !css.is_empty() {
+            style { {css} }
+        }
+    }
+}
+
+//wraps the content of one of a dictionary's entries so its DictionaryStyles apply to it
+#[component]
+pub fn DictionaryEntry(dictionary: String, children: Element) -> Element {
+    rsx! {
+        div {
+            class: "dictionary-entry",
+            "data-dictionary": dictionary,
+            {children}
+        }
+    }
+}
+
+//the settings field for the user's own css for a dictionary. onsave gets None
+//when the field is left empty, which removes the overrides
+#[component]
+pub fn UserStylesSettings(dictionary: String, user_styles: Option<String>, onsave: EventHandler<Option<String>>) -> Element {
+    let mut draft = use_signal(|| user_styles.unwrap_or_default());
+
+    rsx! {
+        div {
+            class: "user-styles-settings",
+            label { "Custom CSS for {dictionary}" }
+            textarea {
+                value: "{draft}",
+                oninput: move |event| draft.set(event.value()),
+            }
+            button {
+                onclick: move |_| {
+                    let css = draft();
+                    onsave.call(if css.trim().is_empty() { None } else { Some(css) });
+                },
+                "Save"
+            }
+        }
+    }
+}
@@ -1,2 +1,4 @@
 mod structured_content;
 pub use structured_content::StructuredContent;
+mod dictionary_styles;
+pub use dictionary_styles::{DictionaryEntry, DictionaryStyles, UserStylesSettings};
@@ -1,6 +1,8 @@
 use dioxus::prelude::*;
 use dioxus_core::AttributeValue;
 use schemas::*;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
 
 #[component]
 pub fn StructuredContent(node: StructuredContentNode) -> Element {
@@ -40,50 +42,87 @@ macro_rules! push_attribute {
     }
 }
 
+//the property names and values come from schemas, so the html renderer styles things the same way
 fn style_into_vec(style: StructuredContentStyle) -> Vec<Attribute> {
-    let mut attributes: Vec<Attribute> = Vec::with_capacity(27);
-    let namespace = Some("style");
+    style
+        .css_declarations()
+        .into_iter()
+        .map(|(name, value)| Attribute {
+            name,
+            value: AttributeValue::Text(value),
+            namespace: Some("style"),
+            volatile: false,
+        })
+        .collect()
+}
+
+//the data keys yomitan's own dictionaries and the big community ones use,
+//their attribute names are written out so they never have to be allocated
+const KNOWN_DATA_ATTRIBUTES: &[(&str, &str)] = &[
+    ("content", "data-sc-content"),
+    ("class", "data-sc-class"),
+    ("code", "data-sc-code"),
+    ("name", "data-sc-name"),
+    ("category", "data-sc-category"),
+    ("language", "data-sc-language"),
+    ("sense-number", "data-sc-sense-number"),
+    ("senseNumber", "data-sc-sense-number"),
+    ("wordClass", "data-sc-word-class"),
+];
+
+//how many other data keys get an attribute name, keys after that are left out
+const MAX_INTERNED_ATTRIBUTES: usize = 256;
+
+//attribute names have to be &'static str but data keys come from the dictionary.
+//keys that aren't known get their name leaked the first time they're seen, up to
+//MAX_INTERNED_ATTRIBUTES of them so a dictionary with made up keys can't grow it forever
+fn interned_attribute_name(key: &str) -> Option<&'static str> {
+    if let Some((_, name)) = KNOWN_DATA_ATTRIBUTES.iter().find(|(known, _)| *known == key) {
+        return Some(name);
+    }
+
+    static NAMES: OnceLock<Mutex<HashMap<String, &'static str>>> = OnceLock::new();
+
+    let mut names = NAMES.get_or_init(Default::default).lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(name) = names.get(key) {
+        return Some(name);
+    }
+    if names.len() >= MAX_INTERNED_ATTRIBUTES {
+        return None;
+    }
 
-    push_attribute!("font-style", namespace, &style.font_style, attributes);
-    push_attribute!("font-weight", namespace, &style.font_weight, attributes);
-    push_attribute!("font-size", namespace, &style.font_size, attributes);
-    push_attribute!("color", namespace, &style.color, attributes);
-    push_attribute!("background", namespace, &style.background, attributes);
-    push_attribute!("background-color", namespace, &style.background_color, attributes);
-    push_attribute!("border-style", namespace, &style.border_style, attributes);
-    push_attribute!("border-radius", namespace, &style.border_radius, attributes);
-    push_attribute!("border-width", namespace, &style.border_width, attributes);
-    push_attribute!("clip-path", namespace, &style.clip_path, attributes);
-    push_attribute!("vertical-align", namespace, &style.vertical_align, attributes);
-    push_attribute!("text_align", namespace, &style.text_align, attributes);
-    push_attribute!("text-shadow", namespace, &style.text_shadow, attributes);
-    push_attribute!("margin", namespace, &style.margin, attributes);
-    push_attribute!("margin-top", namespace, &style.margin_top, attributes);
-    push_attribute!("margin-left", namespace, &style.margin_left, attributes);
-    push_attribute!("margin-right", namespace, &style.margin_right, attributes);
-    push_attribute!("margin-bottom", namespace, &style.margin_bottom, attributes);
-    push_attribute!("padding", namespace, &style.padding, attributes);
-    push_attribute!("padding-top", namespace, &style.padding_top, attributes);
-    push_attribute!("padding-left", namespace, &style.padding_left, attributes);
-    push_attribute!("padding-right", namespace, &style.padding_right, attributes);
-    push_attribute!("padding-bottom", namespace, &style.padding_bottom, attributes);
-    push_attribute!("word-break", namespace, &style.word_break, attributes);
-    push_attribute!("white-space", namespace, &style.white_space, attributes);
-    push_attribute!("cursor", namespace, &style.cursor, attributes);
-    push_attribute!("list-style-type", namespace, &style.list_style_type, attributes);
+    let name: &'static str = Box::leak(data_attribute_name(key).into_boxed_str());
+    names.insert(key.to_string(), name);
+    Some(name)
+}
 
-    attributes
+//yomitan takes each key in data and adds an attribute with name "data-sc-{key}",
+//dictionary stylesheets select on these
+fn data_into_vec(data: &Option<StructuredContentData>, attributes: &mut Vec<Attribute>) {
+    if let Some(data) = data {
+        for (key, value) in data {
+            let Some(name) = interned_attribute_name(key) else {
+                continue;
+            };
+            attributes.push(Attribute {
+                name,
+                value: AttributeValue::Text(value.clone()),
+                namespace: None,
+                volatile: false,
+            });
+        }
+    }
 }
 
-//TODO data
-//yomitan takes each key in data and adds an attribute with name "data-sc-{key}"
-//borrow issue: attributes take a borrowed string as the name, so that name can't outlive where it came from
 fn sc_line_break(elem: &LineBreakElement) -> Element {
+    let mut element_attrs: Vec<Attribute> = vec![];
+    data_into_vec(&elem.data, &mut element_attrs);
 
     match elem.tag {
         LineBreakElementTag::Br => {
             rsx! {
                 br {
+                    ..element_attrs,
                 }
             }
         }
@@ -96,6 +135,7 @@ pub fn sc_styled(elem: &StyledElement) -> Element {
 
     push_attribute!("title", None, elem.title.as_ref(), element_attrs);
     push_attribute!("lang", None, elem.lang.as_ref(), element_attrs);
+    data_into_vec(&elem.data, &mut element_attrs);
 
     //TODO: think of a way to simplify this.
     //Can't use macros (I don't think), no way to convert elem.tag.to_string to a literal.
@@ -184,6 +224,7 @@ fn sc_unstyled(elem: &UnstyledElement) -> Element {
     let mut element_attrs: Vec<Attribute> = vec![];
 
     push_attribute!("lang", None, elem.lang.as_ref(), element_attrs);
+    data_into_vec(&elem.data, &mut element_attrs);
     match elem.tag {
         UnstyledElementTag::Ruby => {
             rsx! {
@@ -275,6 +316,7 @@ fn sc_table(elem: &TableElement) -> Element {
     push_attribute!("colspan", None, elem.col_span.as_ref(), element_attrs);
     push_attribute!("rowspan", None, elem.row_span.as_ref(), element_attrs);
     push_attribute!("lang", None, elem.lang.as_ref(), element_attrs);
+    data_into_vec(&elem.data, &mut element_attrs);
 
     match elem.tag {
         TableElementTag::Td => {
@@ -306,7 +348,7 @@ fn sc_image(elem: &ImageElement) -> Element {
     let mut element_attrs: Vec<Attribute> = vec![];
 
     element_attrs.push(Attribute { name: "path", value: AttributeValue::Text(elem.path.clone()), namespace: None, volatile: false});
-    //TODO data
+    data_into_vec(&elem.data, &mut element_attrs);
     push_attribute!("width", None, elem.width, element_attrs);
     push_attribute!("height", None, elem.height, element_attrs);
     push_attribute!("title", None, &elem.title, element_attrs);
@@ -338,7 +380,7 @@ fn sc_link(elem: &LinkElement) -> Element {
     let mut element_attrs: Vec<Attribute> = vec![];
 
     element_attrs.push(Attribute { name: "href", value: AttributeValue::Text(elem.href.clone()), namespace: None, volatile: false});
-    //TODO data
+    //links have no data in the structured content schema, so there's nothing to add here
     push_attribute!("lang", None, &elem.lang, element_attrs);
 
     match elem.tag {
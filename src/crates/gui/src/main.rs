@@ -1,6 +1,6 @@
-use std::{fs::File, io::BufReader};
+use std::{fs, fs::File, io::BufReader, path::PathBuf, rc::Rc};
 
-use components::StructuredContent;
+use components::{DictionaryEntry, DictionaryStyles, StructuredContent, UserStylesSettings};
 //use components::Hero;
 use dioxus::prelude::*;
 use dioxus_elements::{data, datalist};
@@ -9,6 +9,7 @@ use schemas::{
     StyledElement, TagElement, TermDefinition,
 };
 use serde::Deserialize;
+use yomi_dict_db::YomitanDatabase;
 mod components;
 
 const FAVICON: Asset = asset!("/assets/favicon.ico");
@@ -18,11 +19,73 @@ fn main() {
     dioxus::launch(App);
 }
 
+//where the app reads from, given as: gui [term bank] [database] [dictionary title]
+#[derive(Clone)]
+struct Config {
+    term_bank: PathBuf,
+    database: PathBuf,
+    //the title the dictionary's styles are stored under in the database,
+    //the name of the folder the term bank is in when it isn't given
+    dictionary: String,
+}
+
+impl Config {
+    fn from_args() -> Config {
+        let mut args = std::env::args().skip(1);
+        //let path = "C:\\code\\yomidb\\testzip\\single_term_bank_1.json";
+        //let path = "C:\\code\\yomidb\\testzip\\term_bank_1.json";
+        let term_bank = PathBuf::from(
+            args.next()
+                .unwrap_or(String::from("/home/drags/code/yomidb/testzip/single_term_bank_1.json")),
+        );
+        let database = PathBuf::from(args.next().unwrap_or(String::from("test.db")));
+        let dictionary = args.next().unwrap_or_else(|| {
+            term_bank
+                .parent()
+                .and_then(|dir| dir.file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        });
+
+        Config {
+            term_bank,
+            database,
+            dictionary,
+        }
+    }
+}
+
 #[component]
 fn App() -> Element {
-    //let path = "C:\\code\\yomidb\\testzip\\single_term_bank_1.json";
-    let path = "/home/drags/code/yomidb/testzip/single_term_bank_1.json";
-    //let path = "C:\\code\\yomidb\\testzip\\term_bank_1.json";
+    let config = use_hook(Config::from_args);
+    let path = config.term_bank.as_path();
+    let dictionary = config.dictionary.clone();
+    let db = use_hook(|| {
+        YomitanDatabase::open_database(&config.database)
+            .map(Rc::new)
+            .map_err(|err| err.to_string())
+    });
+    let db = match db {
+        Ok(db) => db,
+        Err(err) => return rsx! { "Fail {err}" },
+    };
+    //what the importer stored, a dictionary that isn't imported has neither
+    let stored = db.dictionary_styles(&dictionary).ok().flatten().unwrap_or_default();
+    //a dictionary's styles.css sits next to its term banks
+    let styles = stored.styles.or_else(|| {
+        path.parent()
+            .and_then(|dir| fs::read_to_string(dir.join("styles.css")).ok())
+    });
+    let mut user_styles = use_signal(|| stored.user_styles);
+    let save_user_styles = {
+        let (db, dictionary) = (db.clone(), dictionary.clone());
+        move |css: Option<String>| {
+            if let Err(err) = db.set_dictionary_user_styles(&dictionary, css.as_deref()) {
+                println!("Fail {}", err);
+            }
+            user_styles.set(css);
+        }
+    };
     let file = File::open(path)?;
     let reader = BufReader::new(file);
     let jd = &mut serde_json::Deserializer::from_reader(reader);
@@ -35,7 +98,11 @@ fn App() -> Element {
                     DetailedDefinition::StructuredContent(node) => {
                         println!("{:?}", node);
                         rsx! {
-                            StructuredContent { node: node.clone() }
+                            DictionaryStyles { dictionary: dictionary.clone(), styles, user_styles: user_styles() }
+                            UserStylesSettings { dictionary: dictionary.clone(), user_styles: user_styles(), onsave: save_user_styles }
+                            DictionaryEntry { dictionary,
+                                StructuredContent { node: node.clone() }
+                            }
                         }
                     }
                     _ => panic!("3"),
@@ -0,0 +1,326 @@
+use serde::de::{DeserializeOwned, Error as _};
+use serde_json::error::Category;
+use std::fmt;
+use std::io::{BufRead, BufReader, ErrorKind, Read};
+use std::marker::PhantomData;
+use crate::dictionary_index_schema::*;
+use crate::kanji_bank_schema::*;
+use crate::legacy_schema::*;
+use crate::meta_schema::*;
+use crate::tag_bank_schema::*;
+use crate::term_bank_schema::*;
+
+//an entry of a bank that couldn't be read
+#[derive(Debug)]
+pub struct BankError {
+    //index of the entry in the bank, None when the problem is with the bank itself,
+    //like it not being an array
+    pub entry: Option<usize>,
+    //where in the bank the problem is, starting with the entry, like [12][5][0].content
+    pub path: String,
+    pub source: serde_json::Error,
+}
+
+impl BankError {
+    //each entry is parsed on its own, so the line and column serde_json
+    //adds to its messages are relative to the entry and get left out
+    pub fn message(&self) -> String {
+        let message = self.source.to_string();
+        match message.rsplit_once(" at line ") {
+            Some((message, _)) if self.entry.is_some() && self.source.line() > 0 => message.to_string(),
+            _ => message,
+        }
+    }
+}
+
+impl fmt::Display for BankError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path.is_empty() {
+            true => write!(f, "{}", self.message()),
+            false => write!(f, "at {}: {}", self.path, self.message()),
+        }
+    }
+}
+
+impl std::error::Error for BankError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+//the path serde_path_to_error gives inside of an entry, put after the entry's index
+fn entry_path(entry: usize, inner: &str) -> String {
+    match inner {
+        "." => format!("[{}]", entry),
+        _ if inner.starts_with('[') => format!("[{}]{}", entry, inner),
+        _ => format!("[{}].{}", entry, inner),
+    }
+}
+
+//reads the entries of a bank one at a time instead of the whole array at once,
+//so only a single entry has to be in memory no matter how big the bank is.
+//an entry that is valid json but doesn't match the schema gives an error and
+//the entries after it are still read, anything that breaks the json itself ends the bank
+pub struct BankReader<T, R> {
+    reader: BufReader<R>,
+    //the json of the entry being read
+    buffer: Vec<u8>,
+    entry: usize,
+    started: bool,
+    finished: bool,
+    marker: PhantomData<T>,
+}
+
+pub type TermBankReader<R> = BankReader<TermInformation, R>;
+pub type TermMetaBankReader<R> = BankReader<TermMeta, R>;
+pub type KanjiBankReader<R> = BankReader<KanjiInformation, R>;
+pub type KanjiMetaBankReader<R> = BankReader<KanjiMetaData, R>;
+pub type TagBankReader<R> = BankReader<TagInformation, R>;
+pub type TermBankV1Reader<R> = BankReader<TermInformationV1, R>;
+pub type KanjiBankV1Reader<R> = BankReader<KanjiInformationV1, R>;
+
+impl<T: DeserializeOwned, R: Read> BankReader<T, R> {
+    pub fn new(reader: R) -> BankReader<T, R> {
+        BankReader {
+            reader: BufReader::new(reader),
+            buffer: Vec::new(),
+            entry: 0,
+            started: false,
+            finished: false,
+            marker: PhantomData,
+        }
+    }
+
+    fn peek(&mut self) -> std::io::Result<Option<u8>> {
+        loop {
+            match self.reader.fill_buf() {
+                Ok(buffer) => return Ok(buffer.first().copied()),
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn next_byte(&mut self) -> std::io::Result<Option<u8>> {
+        let byte = self.peek()?;
+        if byte.is_some() {
+            self.reader.consume(1);
+        }
+        Ok(byte)
+    }
+
+    //skips whitespace and returns the byte after it without consuming it
+    fn skip_whitespace(&mut self) -> std::io::Result<Option<u8>> {
+        while let Some(byte) = self.peek()? {
+            if !byte.is_ascii_whitespace() {
+                return Ok(Some(byte));
+            }
+            self.reader.consume(1);
+        }
+        Ok(None)
+    }
+
+    //copies the next entry into buffer. arrays, objects and strings end at their closing
+    //character, anything else ends at the comma or bracket after it
+    fn read_entry(&mut self) -> std::io::Result<()> {
+        self.buffer.clear();
+        let mut depth = 0usize;
+        let mut in_string = false;
+        let mut escaped = false;
+
+        while let Some(byte) = self.peek()? {
+            if depth == 0 && !in_string && !self.buffer.is_empty() {
+                let first = self.buffer[0];
+                let closed = matches!(first, b'[' | b'{' | b'"');
+                if closed || byte == b',' || byte == b']' || byte.is_ascii_whitespace() {
+                    break;
+                }
+            }
+
+            self.reader.consume(1);
+            self.buffer.push(byte);
+
+            if in_string {
+                match byte {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => in_string = false,
+                    _ => (),
+                }
+            } else {
+                match byte {
+                    b'"' => in_string = true,
+                    b'[' | b'{' => depth += 1,
+                    b']' | b'}' => depth = depth.saturating_sub(1),
+                    _ => (),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    //an error with the bank itself, nothing after it can be read
+    fn bank_error(&mut self, source: serde_json::Error) -> Option<Result<T, BankError>> {
+        self.finished = true;
+        Some(Err(BankError {
+            entry: None,
+            path: String::new(),
+            source,
+        }))
+    }
+
+    fn read_next(&mut self) -> Option<Result<T, BankError>> {
+        if self.finished {
+            return None;
+        }
+
+        if !self.started {
+            self.started = true;
+            match self.skip_whitespace() {
+                Ok(Some(b'[')) => self.reader.consume(1),
+                Ok(_) => return self.bank_error(serde_json::Error::custom("expected the bank to be an array")),
+                Err(err) => return self.bank_error(serde_json::Error::io(err)),
+            }
+            match self.skip_whitespace() {
+                Ok(Some(b']')) => {
+                    self.finished = true;
+                    return None;
+                }
+                Ok(_) => (),
+                Err(err) => return self.bank_error(serde_json::Error::io(err)),
+            }
+        } else {
+            match self.skip_whitespace().and_then(|_| self.next_byte()) {
+                Ok(Some(b',')) => (),
+                Ok(Some(b']')) => {
+                    self.finished = true;
+                    return None;
+                }
+                Ok(_) => {
+                    let message = format!("expected , or ] after entry {}", self.entry - 1);
+                    return self.bank_error(serde_json::Error::custom(message));
+                }
+                Err(err) => return self.bank_error(serde_json::Error::io(err)),
+            }
+        }
+
+        let entry = self.entry;
+        self.entry += 1;
+
+        if let Err(err) = self.skip_whitespace().and_then(|_| self.read_entry()) {
+            return self.bank_error(serde_json::Error::io(err));
+        }
+
+        let jd = &mut serde_json::Deserializer::from_slice(&self.buffer);
+        match serde_path_to_error::deserialize(jd) {
+            Ok(value) => Some(Ok(value)),
+            Err(err) => {
+                let path = entry_path(entry, &err.path().to_string());
+                let source = err.into_inner();
+                //the json is broken, so where the next entry starts isn't known
+                if source.classify() != Category::Data {
+                    self.finished = true;
+                }
+                Some(Err(BankError {
+                    entry: Some(entry),
+                    path,
+                    source,
+                }))
+            }
+        }
+    }
+}
+
+impl<T: DeserializeOwned, R: Read> Iterator for BankReader<T, R> {
+    type Item = Result<T, BankError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_next()
+    }
+}
+
+//term banks of any format as format 3 terms, format 1 entries get converted as they're read
+pub fn read_term_bank<'a, R: Read + 'a>(reader: R, format: &Format) -> Box<dyn Iterator<Item = Result<TermInformation, BankError>> + 'a> {
+    match format {
+        Format::V1 => Box::new(TermBankV1Reader::new(reader).map(|entry| entry.map(TermInformation::from))),
+        Format::V2 | Format::V3 => Box::new(TermBankReader::new(reader)),
+    }
+}
+
+//kanji banks of any format as format 3 kanji
+pub fn read_kanji_bank<'a, R: Read + 'a>(reader: R, format: &Format) -> Box<dyn Iterator<Item = Result<KanjiInformation, BankError>> + 'a> {
+    match format {
+        Format::V1 => Box::new(KanjiBankV1Reader::new(reader).map(|entry| entry.map(KanjiInformation::from))),
+        Format::V2 | Format::V3 => Box::new(KanjiBankReader::new(reader)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn read(bank: &str) -> Vec<Result<Value, BankError>> {
+        BankReader::<Value, _>::new(bank.as_bytes()).collect()
+    }
+
+    fn values(bank: &str) -> Vec<Value> {
+        read(bank).into_iter().map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn strings_with_json_characters() {
+        let bank = r#"[["a]b", "c,d"], ["e\"f", "g\\"], "h\\\"]"]"#;
+        assert_eq!(
+            values(bank),
+            vec![json!(["a]b", "c,d"]), json!(["e\"f", "g\\"]), json!("h\\\"]")]
+        );
+    }
+
+    #[test]
+    fn empty_bank_and_whitespace() {
+        assert!(values("[]").is_empty());
+        assert!(values(" \r\n[ \t ]\n").is_empty());
+        assert_eq!(values("[1,2,3]"), vec![json!(1), json!(2), json!(3)]);
+        assert_eq!(values("\n[\n  1 ,\n\t{\"a\" : [ ]}\r\n,null\n]\n"), vec![json!(1), json!({"a": []}), Value::Null]);
+    }
+
+    #[test]
+    fn top_level_not_an_array() {
+        let entries = read(r#"{"a": 1}"#);
+        assert_eq!(entries.len(), 1);
+        let err = entries.into_iter().next().unwrap().unwrap_err();
+        assert_eq!(err.entry, None);
+        assert!(err.path.is_empty());
+    }
+
+    #[test]
+    fn recovers_after_bad_entry() {
+        let bank = r#"[["n", "partOfSpeech", 0, "noun", 0], ["v", "partOfSpeech", "first", "verb", 0], ["adj", "partOfSpeech", 1, "adjective", 0]]"#;
+        let entries: Vec<_> = TagBankReader::new(bank.as_bytes()).collect();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].as_ref().unwrap().name, "n");
+        let err = entries[1].as_ref().unwrap_err();
+        assert_eq!(err.entry, Some(1));
+        assert_eq!(err.path, "[1][2]");
+        assert_eq!(entries[2].as_ref().unwrap().name, "adj");
+    }
+
+    #[test]
+    fn truncated_entry_ends_the_bank() {
+        let entries = read(r#"[[1, 2], [3, "fo"#);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(*entries[0].as_ref().unwrap(), json!([1, 2]));
+        let err = entries[1].as_ref().unwrap_err();
+        assert_eq!(err.entry, Some(1));
+        assert_eq!(err.source.classify(), Category::Eof);
+    }
+
+    #[test]
+    fn missing_separator_ends_the_bank() {
+        let entries = read("[1 2, 3]");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(*entries[0].as_ref().unwrap(), json!(1));
+        assert_eq!(entries[1].as_ref().unwrap_err().entry, None);
+    }
+}
@@ -1,19 +1,27 @@
 use serde::{Serialize, Deserialize};
 use serde_repr::*;
+use std::collections::HashMap;
+use strum::{Display, EnumString};
 use crate::iso_languages::IsoLanguageCode;
 
-#[derive(Serialize, Deserialize, Debug)]
+//tag metadata from the index of a version 1 or 2 dictionary,
+//version 3 puts tags in tag banks instead
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TagInfo {
-    category: Option<String>,
-    order: Option<i32>,
-    notes: Option<String>,
-    score: Option<i32>
+    pub category: Option<String>,
+    pub order: Option<i32>,
+    pub notes: Option<String>,
+    pub score: Option<i32>
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+//how to read the values in a frequency dictionary
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Display, EnumString)]
 #[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
 pub enum FrequencyMode {
-    OccurenceBased,
+    //how often the term shows up, bigger numbers are more common
+    OccurrenceBased,
+    //position in a list of the most common terms, smaller numbers are more common
     RankBased
 }
 
@@ -35,6 +43,10 @@ pub struct DictionaryIndex {
     pub revision: String,
     pub sequenced: Option<bool>,
     pub format: Option<Format>,
+    //format 1 dictionaries call the format version, some later ones have both.
+    //written back out as it was read so an index with only version keeps its format
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<Format>,
     pub author: Option<String>,
     pub is_updatable: Option<bool>,
     pub index_url: Option<String>,
@@ -43,4 +55,46 @@ pub struct DictionaryIndex {
     pub description: Option<String>,
     pub attribution: Option<String>,
     pub source_language: Option<IsoLanguageCode>,
+    pub target_language: Option<IsoLanguageCode>,
+    pub minimum_yomitan_version: Option<String>,
+    pub frequency_mode: Option<FrequencyMode>,
+    //tag name to its metadata
+    pub tag_meta: Option<HashMap<String, TagInfo>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_with_format_and_version() {
+        let index: DictionaryIndex = serde_json::from_str(
+            r#"{"title":"Test","revision":"1","format":3,"version":3,"sequenced":true}"#,
+        )
+        .unwrap();
+        assert!(matches!(index.format, Some(Format::V3)));
+        assert!(matches!(index.version, Some(Format::V3)));
+
+        let json = serde_json::to_string(&index).unwrap();
+        assert!(json.contains(r#""format":3"#));
+        assert!(json.contains(r#""version":3"#));
+    }
+
+    #[test]
+    fn index_with_only_version() {
+        let index: DictionaryIndex = serde_json::from_str(r#"{"title":"Test","revision":"1","version":1}"#).unwrap();
+        assert!(index.format.is_none());
+        assert!(matches!(index.version, Some(Format::V1)));
+
+        let json = serde_json::to_string(&index).unwrap();
+        let index: DictionaryIndex = serde_json::from_str(&json).unwrap();
+        assert!(matches!(index.format.or(index.version), Some(Format::V1)));
+    }
+
+    #[test]
+    fn index_without_version() {
+        let index: DictionaryIndex = serde_json::from_str(r#"{"title":"Test","revision":"1","format":3}"#).unwrap();
+        let json = serde_json::to_string(&index).unwrap();
+        assert!(!json.contains("\"version\""));
+    }
 }
@@ -0,0 +1,592 @@
+use crate::term_bank_schema::*;
+use std::fmt::Write;
+
+//turns the path of an image in a dictionary into the url the html should load it from,
+//like a file on disk, a data: url or a route served by a local server.
+//returning None leaves the image out
+pub trait ImageResolver {
+    fn resolve(&self, path: &str) -> Option<String>;
+}
+
+//uses image paths as they are in the dictionary
+pub struct KeepPaths;
+
+impl ImageResolver for KeepPaths {
+    fn resolve(&self, path: &str) -> Option<String> {
+        Some(path.to_string())
+    }
+}
+
+impl<F: Fn(&str) -> Option<String>> ImageResolver for F {
+    fn resolve(&self, path: &str) -> Option<String> {
+        self(path)
+    }
+}
+
+//a text decoration can be a list of lines, which css wants separated by spaces
+fn text_decoration_line(line: &TextDecorationLine) -> String {
+    match line {
+        TextDecorationLine::TextDecorationArray(lines) => {
+            lines.iter().map(text_decoration_line).collect::<Vec<String>>().join(" ")
+        }
+        _ => line.to_string(),
+    }
+}
+
+impl StructuredContentStyle {
+    //every property that is set, as css property names and values.
+    //this is the one mapping from structured content styles to css, renderers should use it
+    pub fn css_declarations(&self) -> Vec<(&'static str, String)> {
+        let mut declarations = Vec::new();
+
+        macro_rules! declare {
+            ($name:literal, $field:expr) => {
+                if let Some(value) = &$field {
+                    declarations.push(($name, value.to_string()));
+                }
+            };
+        }
+
+        declare!("font-style", self.font_style);
+        declare!("font-weight", self.font_weight);
+        declare!("font-size", self.font_size);
+        declare!("color", self.color);
+        declare!("background", self.background);
+        declare!("background-color", self.background_color);
+        if let Some(line) = &self.text_decoration_line {
+            declarations.push(("text-decoration-line", text_decoration_line(line)));
+        }
+        declare!("text-decoration-style", self.text_decoration_style);
+        declare!("text-decoration-color", self.text_decoration_color);
+        declare!("border-color", self.border_color);
+        declare!("border-style", self.border_style);
+        declare!("border-radius", self.border_radius);
+        declare!("border-width", self.border_width);
+        declare!("clip-path", self.clip_path);
+        declare!("vertical-align", self.vertical_align);
+        declare!("text-align", self.text_align);
+        declare!("text-emphasis", self.text_emphasis);
+        declare!("text-shadow", self.text_shadow);
+        declare!("margin", self.margin);
+        declare!("margin-top", self.margin_top);
+        declare!("margin-left", self.margin_left);
+        declare!("margin-right", self.margin_right);
+        declare!("margin-bottom", self.margin_bottom);
+        declare!("padding", self.padding);
+        declare!("padding-top", self.padding_top);
+        declare!("padding-left", self.padding_left);
+        declare!("padding-right", self.padding_right);
+        declare!("padding-bottom", self.padding_bottom);
+        declare!("word-break", self.word_break);
+        declare!("white-space", self.white_space);
+        declare!("cursor", self.cursor);
+        declare!("list-style-type", self.list_style_type);
+
+        declarations
+    }
+}
+
+//at-rules whose blocks hold more rules, the rules inside get scoped too
+const NESTED_AT_RULES: [&str; 4] = ["media", "supports", "container", "layer"];
+
+//the selector every rule of a dictionary's css is limited to
+fn dictionary_scope(dictionary: &str) -> String {
+    let escaped = dictionary.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("[data-dictionary=\"{}\"]", escaped)
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut stripped = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        stripped.push_str(&rest[..start]);
+        rest = match rest[start + 2..].find("*/") {
+            Some(end) => &rest[start + 2 + end + 2..],
+            None => "",
+        };
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+//byte index of the } that closes the block starting right after open,
+//braces inside of strings don't count
+fn block_end(css: &str, open: usize) -> usize {
+    let mut depth = 0;
+    let mut quote = None;
+    for (index, c) in css[open..].char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '{') => depth += 1,
+            (None, '}') => {
+                depth -= 1;
+                if depth == 0 {
+                    return open + index;
+                }
+            }
+            _ => (),
+        }
+    }
+    css.len()
+}
+
+//byte index of the first character found returns true for that isn't
+//inside of quotes, parentheses or brackets, or escaped with a backslash
+fn find_top_level(css: &str, mut found: impl FnMut(char) -> bool) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, c) in css.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match (quote, c) {
+            (_, '\\') => escaped = true,
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(' | '[') => depth += 1,
+            (None, ')' | ']') => depth = depth.saturating_sub(1),
+            (None, _) if depth == 0 && found(c) => return Some(index),
+            _ => (),
+        }
+    }
+    None
+}
+
+//a selector list split at its commas, leaving the ones in :is(.a, .b)
+//or [data-sc-content="a,b"] alone
+fn split_selectors(prelude: &str) -> Vec<&str> {
+    let mut selectors = Vec::new();
+    let mut rest = prelude;
+    while let Some(comma) = find_top_level(rest, |c| c == ',') {
+        selectors.push(rest[..comma].trim());
+        rest = &rest[comma + 1..];
+    }
+    selectors.push(rest.trim());
+    selectors
+}
+
+fn scope_rules(css: &str, scope: &str, scoped: &mut String) {
+    let mut rest = css.trim_start();
+    while !rest.is_empty() {
+        let Some(open) = find_top_level(rest, |c| c == '{' || c == ';') else {
+            break;
+        };
+
+        //statements like @import and @charset
+        if rest[open..].starts_with(';') {
+            scoped.push_str(rest[..=open].trim());
+            scoped.push('\n');
+            rest = rest[open + 1..].trim_start();
+            continue;
+        }
+
+        let prelude = rest[..open].trim();
+        let close = block_end(rest, open);
+        let block = &rest[open + 1..close.min(rest.len())];
+
+        if let Some(at_rule) = prelude.strip_prefix('@') {
+            let name = at_rule.split(|c: char| c.is_whitespace() || c == '(').next().unwrap_or_default();
+            if NESTED_AT_RULES.contains(&name) {
+                scoped.push_str(prelude);
+                scoped.push_str(" {\n");
+                scope_rules(block, scope, scoped);
+                scoped.push_str("}\n");
+            } else {
+                //@font-face, @keyframes and the like aren't selectors
+                scoped.push_str(&format!("{} {{{}}}\n", prelude, block));
+            }
+        } else {
+            let selectors: Vec<String> = split_selectors(prelude)
+                .into_iter()
+                .map(|selector| format!("{} {}", scope, selector))
+                .collect();
+            scoped.push_str(&format!("{} {{{}}}\n", selectors.join(", "), block));
+        }
+
+        rest = rest.get(close + 1..).unwrap_or_default().trim_start();
+    }
+}
+
+//rewrites every selector in the css so it only matches inside of
+//elements with data-dictionary set to the dictionary's title
+pub fn scope_dictionary_css(css: &str, dictionary: &str) -> String {
+    let mut scoped = String::new();
+    scope_rules(&strip_comments(css), &dictionary_scope(dictionary), &mut scoped);
+    scoped
+}
+
+//yomitan puts each data entry in the element's dataset with an "sc" prefix,
+//so {"wordClass": "noun"} ends up as data-sc-word-class="noun"
+pub fn data_attribute_name(key: &str) -> String {
+    let mut name = String::from("data-sc-");
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            name.push('-');
+            name.push(c.to_ascii_lowercase());
+        } else if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+            name.push(c);
+        }
+    }
+    name
+}
+
+fn escape(text: &str, html: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            _ => html.push(c),
+        }
+    }
+}
+
+//values that could end the declaration early or load something are dropped
+fn safe_css_value(value: &str) -> bool {
+    let lower = value.to_ascii_lowercase();
+    !value.contains([';', '{', '}', '<', '\\']) && !lower.contains("url(") && !lower.contains("expression(")
+}
+
+//links in structured content either search for something (?query=...) or go to a website
+fn safe_href(href: &str) -> bool {
+    let lower = href.to_ascii_lowercase();
+    href.starts_with('?') || href.starts_with('#') || lower.starts_with("https://") || lower.starts_with("http://")
+}
+
+struct HtmlWriter<'a> {
+    html: String,
+    resolver: &'a dyn ImageResolver,
+}
+
+impl HtmlWriter<'_> {
+    fn attribute(&mut self, name: &str, value: &str) {
+        let _ = write!(self.html, " {}=\"", name);
+        escape(value, &mut self.html);
+        self.html.push('"');
+    }
+
+    fn optional_attribute<T: ToString>(&mut self, name: &str, value: &Option<T>) {
+        if let Some(value) = value {
+            self.attribute(name, &value.to_string());
+        }
+    }
+
+    fn data(&mut self, data: &Option<StructuredContentData>) {
+        if let Some(data) = data {
+            //sorted so the same content always gives the same html
+            let mut entries: Vec<(&String, &String)> = data.iter().collect();
+            entries.sort();
+            for (key, value) in entries {
+                self.attribute(&data_attribute_name(key), value);
+            }
+        }
+    }
+
+    fn style(&mut self, declarations: Vec<(&'static str, String)>) {
+        let css: Vec<String> = declarations
+            .into_iter()
+            .filter(|(_, value)| safe_css_value(value))
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect();
+
+        if !css.is_empty() {
+            self.attribute("style", &css.join("; "));
+        }
+    }
+
+    //opens a tag with yomitan's class for it, the attributes get added before calling close_start
+    fn open(&mut self, tag: &str) {
+        let _ = write!(self.html, "<{}", tag);
+        self.attribute("class", &format!("gloss-sc-{}", tag));
+    }
+
+    fn close_start(&mut self) {
+        self.html.push('>');
+    }
+
+    fn close(&mut self, tag: &str, content: &Option<StructuredContentNode>) {
+        self.close_start();
+        if let Some(content) = content {
+            self.node(content);
+        }
+        let _ = write!(self.html, "</{}>", tag);
+    }
+
+    fn node(&mut self, node: &StructuredContentNode) {
+        match node {
+            StructuredContentNode::Text(text) => escape(text, &mut self.html),
+            StructuredContentNode::ChildContent(children) => {
+                for child in children {
+                    self.node(child);
+                }
+            }
+            StructuredContentNode::Variant(element) => self.element(element),
+        }
+    }
+
+    fn element(&mut self, element: &TagElement) {
+        match element {
+            TagElement::LineBreak(elem) => {
+                self.open("br");
+                self.data(&elem.data);
+                self.close_start();
+            }
+            TagElement::Unstyled(elem) => {
+                let tag = elem.tag.to_string();
+                self.open(&tag);
+                self.data(&elem.data);
+                self.optional_attribute("lang", &elem.lang);
+                self.close(&tag, &elem.content);
+            }
+            TagElement::Table(elem) => {
+                let tag = elem.tag.to_string();
+                self.open(&tag);
+                self.data(&elem.data);
+                self.optional_attribute("colspan", &elem.col_span);
+                self.optional_attribute("rowspan", &elem.row_span);
+                self.optional_attribute("lang", &elem.lang);
+                self.style(elem.style.as_ref().map(|style| style.css_declarations()).unwrap_or_default());
+                self.close(&tag, &elem.content);
+            }
+            TagElement::Styled(elem) => {
+                let tag = elem.tag.to_string();
+                self.open(&tag);
+                self.data(&elem.data);
+                self.optional_attribute("title", &elem.title);
+                self.optional_attribute("lang", &elem.lang);
+                self.style(elem.style.as_ref().map(|style| style.css_declarations()).unwrap_or_default());
+                self.close(&tag, &elem.content);
+            }
+            TagElement::Image(elem) => self.image(elem),
+            TagElement::Link(elem) => {
+                self.open("a");
+                if safe_href(&elem.href) {
+                    self.attribute("href", &elem.href);
+                }
+                self.optional_attribute("lang", &elem.lang);
+                self.close("a", &elem.content);
+            }
+        }
+    }
+
+    fn image(&mut self, elem: &ImageElement) {
+        let Some(src) = self.resolver.resolve(&elem.path) else {
+            return;
+        };
+
+        let units = match elem.size_units {
+            Some(SizeUnit::Em) => "em",
+            _ => "px",
+        };
+
+        let mut declarations = Vec::new();
+        if let Some(width) = elem.width {
+            declarations.push(("width", format!("{}{}", width, units)));
+        }
+        if let Some(height) = elem.height {
+            declarations.push(("height", format!("{}{}", height, units)));
+        }
+        if elem.pixelated == Some(true) {
+            declarations.push(("image-rendering", String::from("pixelated")));
+        } else if let Some(rendering) = &elem.image {
+            declarations.push(("image-rendering", rendering.to_string()));
+        }
+        if elem.appearance == Some(ImageAppearance::Monochrome) {
+            declarations.push(("filter", String::from("grayscale(1)")));
+        }
+        if let Some(vertical_align) = &elem.vertical_align {
+            declarations.push(("vertical-align", vertical_align.to_string()));
+        }
+        if let Some(border) = &elem.border {
+            declarations.push(("border", border.clone()));
+        }
+        if let Some(border_radius) = &elem.border_radius {
+            declarations.push(("border-radius", border_radius.clone()));
+        }
+
+        self.open("img");
+        self.data(&elem.data);
+        self.attribute("src", &src);
+        self.optional_attribute("alt", &elem.alt.as_ref().or(elem.description.as_ref()));
+        self.optional_attribute("title", &elem.title);
+        self.style(declarations);
+        self.close_start();
+    }
+}
+
+impl StructuredContentNode {
+    //html that can be put straight into a page, image paths are left as they are
+    pub fn to_html(&self) -> String {
+        self.to_html_with(&KeepPaths)
+    }
+
+    pub fn to_html_with(&self, resolver: &dyn ImageResolver) -> String {
+        let mut writer = HtmlWriter {
+            html: String::new(),
+            resolver,
+        };
+        writer.node(self);
+        writer.html
+    }
+}
+
+impl TermDefinition {
+    pub fn to_html(&self) -> String {
+        self.to_html_with(&KeepPaths)
+    }
+
+    pub fn to_html_with(&self, resolver: &dyn ImageResolver) -> String {
+        let mut html = String::new();
+        match self {
+            TermDefinition::Simple(text) | TermDefinition::Detailed(DetailedDefinition::Text(text)) => {
+                escape(text, &mut html)
+            }
+            TermDefinition::Detailed(DetailedDefinition::StructuredContent(node)) => {
+                html = node.to_html_with(resolver)
+            }
+            TermDefinition::Detailed(DetailedDefinition::Image(image)) => {
+                let element = ImageElement {
+                    tag: ImageElementTag::Img,
+                    data: None,
+                    path: image.path.clone(),
+                    width: image.width,
+                    height: image.height,
+                    title: image.title.clone(),
+                    alt: image.alt.clone(),
+                    description: image.description.clone(),
+                    pixelated: image.pixelated,
+                    image: image.image_rendering.clone(),
+                    appearance: image.appearance.clone(),
+                    background: image.background,
+                    collapsed: image.collapsed,
+                    collapsible: image.collapsible,
+                    vertical_align: None,
+                    border: None,
+                    border_radius: None,
+                    size_units: None,
+                };
+                let mut writer = HtmlWriter { html, resolver };
+                writer.image(&element);
+                html = writer.html;
+            }
+            TermDefinition::Inflection(deinflection) => {
+                escape(&deinflection.uninflected_term, &mut html);
+                if !deinflection.inflection_rules.is_empty() {
+                    html.push_str(" (");
+                    escape(&deinflection.inflection_rules.join(", "), &mut html);
+                    html.push(')');
+                }
+            }
+        }
+        html
+    }
+}
+
+impl TermInformation {
+    pub fn to_html(&self) -> String {
+        self.to_html_with(&KeepPaths)
+    }
+
+    //the definitions as a list, the same way yomitan shows a glossary
+    pub fn to_html_with(&self, resolver: &dyn ImageResolver) -> String {
+        let mut html = String::from("<ul class=\"glossary-list\">");
+        for definition in &self.definitions {
+            html.push_str("<li class=\"glossary-item\">");
+            html.push_str(&definition.to_html_with(resolver));
+            html.push_str("</li>");
+        }
+        html.push_str("</ul>");
+        html
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(json: &str) -> StructuredContentNode {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn escapes_text_and_attributes() {
+        let html = node(r#"{"tag": "span", "title": "\"a\" & 'b'", "content": "<b>x & y</b>"}"#).to_html();
+        assert_eq!(
+            html,
+            "<span class=\"gloss-sc-span\" title=\"&quot;a&quot; &amp; &#39;b&#39;\">&lt;b&gt;x &amp; y&lt;/b&gt;</span>"
+        );
+    }
+
+    #[test]
+    fn javascript_links_are_dropped() {
+        assert!(safe_href("?query=傘"));
+        assert!(safe_href("https://example.com"));
+        assert!(!safe_href("javascript:alert(1)"));
+        assert!(!safe_href("JavaScript:alert(1)"));
+
+        let html = node(r#"{"tag": "a", "href": "javascript:alert(1)", "content": "x"}"#).to_html();
+        assert_eq!(html, "<a class=\"gloss-sc-a\">x</a>");
+    }
+
+    #[test]
+    fn unsafe_css_values_are_rejected() {
+        assert!(safe_css_value("1em"));
+        assert!(!safe_css_value("url(https://example.com/a.png)"));
+        assert!(!safe_css_value("URL(a.png)"));
+        assert!(!safe_css_value("expression(alert(1))"));
+        assert!(!safe_css_value("red; background: blue"));
+
+        let html = node(r#"{"tag": "div", "style": {"color": "red", "background": "url(a.png)"}}"#).to_html();
+        assert_eq!(html, "<div class=\"gloss-sc-div\" style=\"color: red\"></div>");
+    }
+
+    #[test]
+    fn data_attribute_names() {
+        assert_eq!(data_attribute_name("wordClass"), "data-sc-word-class");
+        assert_eq!(data_attribute_name("content"), "data-sc-content");
+        assert_eq!(data_attribute_name("a\" onclick=\"x"), "data-sc-aonclickx");
+
+        let html = node(r#"{"tag": "span", "data": {"wordClass": "noun"}}"#).to_html();
+        assert_eq!(html, "<span class=\"gloss-sc-span\" data-sc-word-class=\"noun\"></span>");
+    }
+
+    #[test]
+    fn image_resolver_rewrites_src() {
+        let image = node(r#"{"tag": "img", "path": "img/a.png", "alt": "a"}"#);
+        assert_eq!(image.to_html(), "<img class=\"gloss-sc-img\" src=\"img/a.png\" alt=\"a\">");
+
+        let resolver = |path: &str| Some(format!("media://dictionary/{}", path));
+        assert_eq!(
+            image.to_html_with(&resolver),
+            "<img class=\"gloss-sc-img\" src=\"media://dictionary/img/a.png\" alt=\"a\">"
+        );
+
+        let missing = |_: &str| None;
+        assert_eq!(image.to_html_with(&missing), "");
+    }
+
+    #[test]
+    fn commas_inside_selectors() {
+        let css = r#":is(.a, .b) > span, [data-sc-content="a,b"] { color: red; }"#;
+        assert_eq!(
+            scope_dictionary_css(css, "Dict"),
+            "[data-dictionary=\"Dict\"] :is(.a, .b) > span, [data-dictionary=\"Dict\"] [data-sc-content=\"a,b\"] { color: red; }\n"
+        );
+    }
+
+    #[test]
+    fn brace_inside_attribute_value() {
+        let css = r#"[data-sc-content="{"] { color: red; } .b { color: blue; }"#;
+        assert_eq!(
+            scope_dictionary_css(css, "Dict"),
+            "[data-dictionary=\"Dict\"] [data-sc-content=\"{\"] { color: red; }\n[data-dictionary=\"Dict\"] .b { color: blue; }\n"
+        );
+    }
+}
@@ -1,7 +1,9 @@
 use serde::{Serialize, Deserialize};
+use strum::{Display, EnumString};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Display, EnumString)]
 #[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
 #[allow(non_camel_case_types)]
 pub enum IsoLanguageCode {
     aa,
@@ -14,6 +16,7 @@ pub enum IsoLanguageCode {
     an,
     ar,
     #[serde(rename = "as")]
+    #[strum(serialize = "as")]
     As,
     av,
     ay,
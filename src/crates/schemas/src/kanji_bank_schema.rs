@@ -7,12 +7,17 @@ pub type KanjiStats = HashMap<String, String>;
 
 #[derive(Debug)]
 pub struct KanjiInformation {
-    kanji: String,
-    onyomi: String,
-    kunyomi: String,
-    tags: String,
-    meanings: Vec<String>,
-    stats: KanjiStats,
+    pub kanji: String,
+    pub onyomi: Vec<String>,
+    pub kunyomi: Vec<String>,
+    pub tags: String,
+    pub meanings: Vec<String>,
+    pub stats: KanjiStats,
+}
+
+//readings are space separated in the bank, they get split into a list here
+pub(crate) fn split_readings(readings: &str) -> Vec<String> {
+    readings.split_whitespace().map(String::from).collect()
 }
 
 //struct tuple used as an intermediary for serializing/deserializing
@@ -38,8 +43,8 @@ impl<'de> Deserialize<'de> for KanjiInformation {
 
         Ok(KanjiInformation {
             kanji,
-            onyomi,
-            kunyomi,
+            onyomi: split_readings(&onyomi),
+            kunyomi: split_readings(&kunyomi),
             tags,
             meanings,
             stats
@@ -55,8 +60,8 @@ impl Serialize for KanjiInformation {
         //TODO change to constant
         let mut seq = serializer.serialize_seq(Some(6))?;
         seq.serialize_element(&self.kanji)?;
-        seq.serialize_element(&self.onyomi)?;
-        seq.serialize_element(&self.kunyomi)?;
+        seq.serialize_element(&self.onyomi.join(" "))?;
+        seq.serialize_element(&self.kunyomi.join(" "))?;
         seq.serialize_element(&self.tags)?;
         seq.serialize_element(&self.meanings)?;
         seq.serialize_element(&self.stats)?;
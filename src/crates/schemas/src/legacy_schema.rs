@@ -0,0 +1,167 @@
+use serde::de::{Deserializer, Error, SeqAccess, Visitor};
+use serde::Deserialize;
+use std::fmt;
+use std::marker::PhantomData;
+use crate::kanji_bank_schema::*;
+use crate::term_bank_schema::*;
+
+//banks from format 1 dictionaries, before yomichan moved the definitions of a term
+//and the meanings of a kanji into a list of their own. format 2 dictionaries already
+//use the same bank layout as format 3, just without structured content, so
+//DictionaryTermBankV3 and DictionaryKanjiBankV3 read them as they are
+
+//[expression, reading, definition tags, rules, score, definition, definition, ...]
+//there is no sequence number or term tags
+#[derive(Debug)]
+pub struct TermInformationV1 {
+    pub term: String,
+    pub reading: String,
+    pub definition_tags: Option<String>,
+    pub deinflectors: String,
+    pub popularity: i32,
+    pub definitions: Vec<String>,
+}
+
+//[character, onyomi, kunyomi, tags, meaning, meaning, ...]
+//there are no stats
+#[derive(Debug)]
+pub struct KanjiInformationV1 {
+    pub kanji: String,
+    pub onyomi: String,
+    pub kunyomi: String,
+    pub tags: String,
+    pub meanings: Vec<String>,
+}
+
+pub type DictionaryTermBankV1 = Vec<TermInformationV1>;
+pub type DictionaryKanjiBankV1 = Vec<KanjiInformationV1>;
+
+//what format 1 terms get as their sequence number, yomitan uses -1 for "no sequence"
+pub const NO_SEQUENCE: i32 = -1;
+
+fn next_field<'de, T, S>(access: &mut S, field: &'static str) -> Result<T, S::Error>
+where
+    T: Deserialize<'de>,
+    S: SeqAccess<'de>,
+{
+    access
+        .next_element::<T>()?
+        .ok_or_else(|| <S::Error as Error>::missing_field(field))
+}
+
+//every element left in the array, which all have to be strings
+fn remaining_strings<'de, S>(access: &mut S) -> Result<Vec<String>, S::Error>
+where
+    S: SeqAccess<'de>,
+{
+    let mut strings = Vec::new();
+    while let Some(string) = access.next_element::<String>()? {
+        strings.push(string);
+    }
+    Ok(strings)
+}
+
+//a visitor for the array entries of the legacy banks, expecting is what
+//serde reports when the entry isn't an array
+struct LegacyVisitor<T> {
+    expecting: &'static str,
+    marker: PhantomData<T>,
+}
+
+impl<T> LegacyVisitor<T> {
+    fn new(expecting: &'static str) -> LegacyVisitor<T> {
+        LegacyVisitor {
+            expecting,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'de> Visitor<'de> for LegacyVisitor<TermInformationV1> {
+    type Value = TermInformationV1;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(self.expecting)
+    }
+
+    fn visit_seq<S>(self, mut access: S) -> Result<Self::Value, S::Error>
+    where
+        S: SeqAccess<'de>,
+    {
+        Ok(TermInformationV1 {
+            term: next_field(&mut access, "expression")?,
+            reading: next_field(&mut access, "reading")?,
+            definition_tags: next_field(&mut access, "definitionTags")?,
+            deinflectors: next_field(&mut access, "rules")?,
+            popularity: next_field(&mut access, "score")?,
+            definitions: remaining_strings(&mut access)?,
+        })
+    }
+}
+
+impl<'de> Visitor<'de> for LegacyVisitor<KanjiInformationV1> {
+    type Value = KanjiInformationV1;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(self.expecting)
+    }
+
+    fn visit_seq<S>(self, mut access: S) -> Result<Self::Value, S::Error>
+    where
+        S: SeqAccess<'de>,
+    {
+        Ok(KanjiInformationV1 {
+            kanji: next_field(&mut access, "character")?,
+            onyomi: next_field(&mut access, "onyomi")?,
+            kunyomi: next_field(&mut access, "kunyomi")?,
+            tags: next_field(&mut access, "tags")?,
+            meanings: remaining_strings(&mut access)?,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for TermInformationV1 {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(LegacyVisitor::<TermInformationV1>::new("format 1 term"))
+    }
+}
+
+impl<'de> Deserialize<'de> for KanjiInformationV1 {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(LegacyVisitor::<KanjiInformationV1>::new("format 1 kanji"))
+    }
+}
+
+impl From<TermInformationV1> for TermInformation {
+    fn from(term: TermInformationV1) -> Self {
+        TermInformation {
+            term: term.term,
+            reading: term.reading,
+            definition_tags: term.definition_tags,
+            deinflectors: term.deinflectors,
+            popularity: term.popularity,
+            definitions: term.definitions.into_iter().map(TermDefinition::Simple).collect(),
+            sequence_number: NO_SEQUENCE,
+            term_tags: String::new(),
+        }
+    }
+}
+
+impl From<KanjiInformationV1> for KanjiInformation {
+    fn from(kanji: KanjiInformationV1) -> Self {
+        KanjiInformation {
+            kanji: kanji.kanji,
+            onyomi: split_readings(&kanji.onyomi),
+            kunyomi: split_readings(&kanji.kunyomi),
+            tags: kanji.tags,
+            meanings: kanji.meanings,
+            stats: KanjiStats::new(),
+        }
+    }
+}
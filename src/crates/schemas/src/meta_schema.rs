@@ -10,6 +10,7 @@ pub enum FrequencyData {
     Number(i32),
     DisplayNumber {
         value: i32,
+        #[serde(rename = "displayValue")]
         display_value: Option<String>,
         reading: Option<String>,
     }
@@ -17,9 +18,9 @@ pub enum FrequencyData {
 
 #[derive(Debug)]
 pub struct KanjiMetaData {
-    character: String,
-    mode: String,
-    data: FrequencyData
+    pub character: String,
+    pub mode: String,
+    pub data: FrequencyData
 }
 
 #[derive(Serialize, Deserialize)]
@@ -63,20 +64,22 @@ impl Serialize for KanjiMetaData {
 
 pub type DictionaryKanjiMetaBankV3 = Vec<KanjiMetaData>;
 
+//WithReading goes first, otherwise untagged would try to read it as a DisplayNumber
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
 pub enum TermMetaFrequencyData {
-    Generic(FrequencyData),
     WithReading{
         reading: String,
         frequency: FrequencyData
-    }
+    },
+    Generic(FrequencyData)
 }
 
 #[derive(Debug)]
 pub struct TermMetaFrequency {
-    expression: String,
-    mode: String,
-    data: TermMetaFrequencyData
+    pub expression: String,
+    pub mode: String,
+    pub data: TermMetaFrequencyData
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -88,42 +91,42 @@ pub enum NumOrArray {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PitchData {
-    position: i32,
-    nasal: Option<NumOrArray>,
-    devoice: Option<NumOrArray>,
-    tags: Option<Vec<String>>
+    pub position: i32,
+    pub nasal: Option<NumOrArray>,
+    pub devoice: Option<NumOrArray>,
+    pub tags: Option<Vec<String>>
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TermMetaPitchData {
-    reading: String,
-    pitches: Vec<PitchData>
+    pub reading: String,
+    pub pitches: Vec<PitchData>
 }
 
 #[derive(Debug)]
 pub struct TermMetaPitch {
-    expression: String,
-    mode: String,
-    data: TermMetaPitchData
+    pub expression: String,
+    pub mode: String,
+    pub data: TermMetaPitchData
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Transcription {
-    ipa: String,
-    tags: Option<Vec<String>>
+    pub ipa: String,
+    pub tags: Option<Vec<String>>
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TermMetaPhoneticData {
-    reading: String,
-    transcriptions: Vec<Transcription>
+    pub reading: String,
+    pub transcriptions: Vec<Transcription>
 }
 
 #[derive(Debug)]
 pub struct TermMetaPhonetic {
-    expression: String,
-    mode: String,
-    data: TermMetaPhoneticData
+    pub expression: String,
+    pub mode: String,
+    pub data: TermMetaPhoneticData
 }
 
 //TermMeta can be TermMetaFrequency, TermMetaPitch, or TermMetaPhonetic
@@ -135,6 +138,53 @@ pub enum TermMeta {
     Phonetic(TermMetaPhonetic)
 }
 
+impl TermMeta {
+    pub fn expression(&self) -> &str {
+        match self {
+            TermMeta::Frequency(freq) => &freq.expression,
+            TermMeta::Pitch(pitch) => &pitch.expression,
+            TermMeta::Phonetic(phonetic) => &phonetic.expression,
+        }
+    }
+
+    //frequencies without a reading apply to every reading of the expression
+    pub fn reading(&self) -> Option<&str> {
+        match self {
+            TermMeta::Frequency(freq) => match &freq.data {
+                TermMetaFrequencyData::WithReading { reading, .. } => Some(reading),
+                TermMetaFrequencyData::Generic(_) => None,
+            },
+            TermMeta::Pitch(pitch) => Some(&pitch.data.reading),
+            TermMeta::Phonetic(phonetic) => Some(&phonetic.data.reading),
+        }
+    }
+
+    pub fn frequency(&self) -> Option<&FrequencyData> {
+        match self {
+            TermMeta::Frequency(freq) => match &freq.data {
+                TermMetaFrequencyData::WithReading { frequency, .. } => Some(frequency),
+                TermMetaFrequencyData::Generic(frequency) => Some(frequency),
+            },
+            _ => None,
+        }
+    }
+}
+
+impl FrequencyData {
+    //the number to sort by. text frequencies like "1234" or "1234 (5%)"
+    //use the number they start with, text without one has no value
+    pub fn value(&self) -> Option<i64> {
+        match self {
+            FrequencyData::Number(value) => Some(*value as i64),
+            FrequencyData::DisplayNumber { value, .. } => Some(*value as i64),
+            FrequencyData::Text(text) => {
+                let digits: String = text.trim_start().chars().take_while(|c| c.is_ascii_digit()).collect();
+                digits.parse().ok()
+            }
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for TermMeta {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -238,4 +288,6 @@ impl Serialize for TermMeta {
         }
         seq.end()
     }
-}
\ No newline at end of file
+}
+
+pub type DictionaryTermMetaBankV3 = Vec<TermMeta>;
\ No newline at end of file
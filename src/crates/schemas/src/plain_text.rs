@@ -0,0 +1,220 @@
+use crate::term_bank_schema::*;
+
+//what to do with the reading of ruby text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Furigana {
+    //漢字[かんじ], the format anki uses for furigana
+    #[default]
+    Bracketed,
+    //漢字
+    Dropped,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PlainTextOptions {
+    pub furigana: Furigana,
+    //puts a space between the text of nodes that would otherwise run together,
+    //like an image's alt text and the link after it. for search indexes, where
+    //that would make one word out of two
+    pub word_breaks: bool,
+}
+
+//put between the cells of a table row
+const CELL_SEPARATOR: &str = " | ";
+
+struct PlainTextWriter {
+    text: String,
+    options: PlainTextOptions,
+    //one entry per list the writer is inside of, numbered lists count their items
+    lists: Vec<Option<usize>>,
+    //how many ruby elements the writer is inside of, a word split up
+    //by its readings stays together even with word_breaks
+    ruby: usize,
+}
+
+impl PlainTextWriter {
+    fn new(options: PlainTextOptions) -> PlainTextWriter {
+        PlainTextWriter {
+            text: String::new(),
+            options,
+            lists: Vec::new(),
+            ruby: 0,
+        }
+    }
+
+    fn push_text(&mut self, text: &str) {
+        let follows_word = self.text.chars().last().is_some_and(|c| !c.is_whitespace());
+        let starts_word = text.chars().next().is_some_and(|c| !c.is_whitespace());
+        if self.options.word_breaks && self.ruby == 0 && follows_word && starts_word {
+            self.text.push(' ');
+        }
+        self.text.push_str(text);
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.text.is_empty() || self.text.ends_with('\n')
+    }
+
+    //starts a new line unless there already is one, so nested blocks don't leave empty lines
+    fn line_break(&mut self) {
+        if !self.at_line_start() {
+            self.text.push('\n');
+        }
+    }
+
+    fn content(&mut self, content: &Option<StructuredContentNode>) {
+        if let Some(content) = content {
+            self.node(content);
+        }
+    }
+
+    fn node(&mut self, node: &StructuredContentNode) {
+        match node {
+            StructuredContentNode::Text(text) => self.push_text(text),
+            StructuredContentNode::ChildContent(children) => {
+                for child in children {
+                    self.node(child);
+                }
+            }
+            StructuredContentNode::Variant(element) => self.element(element),
+        }
+    }
+
+    fn element(&mut self, element: &TagElement) {
+        match element {
+            TagElement::LineBreak(_) => self.text.push('\n'),
+            TagElement::Unstyled(elem) => match elem.tag {
+                UnstyledElementTag::Rt => {
+                    if self.options.furigana == Furigana::Bracketed {
+                        self.text.push('[');
+                        self.content(&elem.content);
+                        self.text.push(']');
+                    }
+                }
+                //the parentheses for browsers that can't show ruby
+                UnstyledElementTag::Rp => (),
+                UnstyledElementTag::Ruby => {
+                    self.ruby += 1;
+                    self.content(&elem.content);
+                    self.ruby -= 1;
+                }
+                //each row goes on its own line, the cells are separated in TagElement::Table
+                UnstyledElementTag::Table
+                | UnstyledElementTag::Thead
+                | UnstyledElementTag::Tbody
+                | UnstyledElementTag::Tfoot
+                | UnstyledElementTag::Tr => {
+                    self.line_break();
+                    self.content(&elem.content);
+                    self.line_break();
+                }
+            },
+            TagElement::Table(elem) => {
+                if !self.at_line_start() {
+                    self.text.push_str(CELL_SEPARATOR);
+                }
+                self.content(&elem.content);
+            }
+            TagElement::Styled(elem) => match elem.tag {
+                StyledElementTag::Span => self.content(&elem.content),
+                StyledElementTag::Ul | StyledElementTag::Ol => {
+                    let counter = match elem.tag {
+                        StyledElementTag::Ol => Some(0),
+                        _ => None,
+                    };
+                    self.lists.push(counter);
+                    self.line_break();
+                    self.content(&elem.content);
+                    self.line_break();
+                    self.lists.pop();
+                }
+                StyledElementTag::Li => {
+                    self.line_break();
+                    let depth = self.lists.len().saturating_sub(1);
+                    self.text.push_str(&"  ".repeat(depth));
+
+                    match self.lists.last_mut() {
+                        Some(Some(number)) => {
+                            *number += 1;
+                            self.text.push_str(&format!("{}. ", number));
+                        }
+                        _ => self.text.push_str("• "),
+                    }
+
+                    self.content(&elem.content);
+                    self.line_break();
+                }
+                StyledElementTag::Div | StyledElementTag::Details | StyledElementTag::Summary => {
+                    self.line_break();
+                    self.content(&elem.content);
+                    self.line_break();
+                }
+            },
+            TagElement::Image(elem) => {
+                if let Some(alt) = &elem.alt {
+                    self.push_text(alt);
+                }
+            }
+            TagElement::Link(elem) => self.content(&elem.content),
+        }
+    }
+
+    fn finish(self) -> String {
+        self.text.trim_end().to_string()
+    }
+}
+
+impl StructuredContentNode {
+    pub fn to_plain_text(&self) -> String {
+        self.to_plain_text_with(PlainTextOptions::default())
+    }
+
+    pub fn to_plain_text_with(&self, options: PlainTextOptions) -> String {
+        let mut writer = PlainTextWriter::new(options);
+        writer.node(self);
+        writer.finish()
+    }
+}
+
+impl TermDefinition {
+    pub fn to_plain_text(&self) -> String {
+        self.to_plain_text_with(PlainTextOptions::default())
+    }
+
+    //inflection definitions come out as the uninflected term followed by its rules, 食べる (past)
+    pub fn to_plain_text_with(&self, options: PlainTextOptions) -> String {
+        match self {
+            TermDefinition::Simple(text) | TermDefinition::Detailed(DetailedDefinition::Text(text)) => text.clone(),
+            TermDefinition::Detailed(DetailedDefinition::StructuredContent(node)) => node.to_plain_text_with(options),
+            TermDefinition::Detailed(DetailedDefinition::Image(image)) => image
+                .alt
+                .clone()
+                .or_else(|| image.description.clone())
+                .unwrap_or_default(),
+            TermDefinition::Inflection(deinflection) => match deinflection.inflection_rules.is_empty() {
+                true => deinflection.uninflected_term.clone(),
+                false => format!(
+                    "{} ({})",
+                    deinflection.uninflected_term,
+                    deinflection.inflection_rules.join(", ")
+                ),
+            },
+        }
+    }
+}
+
+impl TermInformation {
+    pub fn to_plain_text(&self) -> String {
+        self.to_plain_text_with(PlainTextOptions::default())
+    }
+
+    //each definition on its own line, definitions with no text are skipped
+    pub fn to_plain_text_with(&self, options: PlainTextOptions) -> String {
+        self.definitions
+            .iter()
+            .map(|definition| definition.to_plain_text_with(options))
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
@@ -4,10 +4,18 @@ pub mod kanji_bank_schema;
 pub mod tag_bank_schema;
 pub mod term_bank_schema;
 pub mod meta_schema;
+pub mod legacy_schema;
+pub mod bank_reader;
+pub mod html;
+pub mod plain_text;
 
 pub use iso_languages::*;
 pub use dictionary_index_schema::*;
 pub use kanji_bank_schema::*;
 pub use tag_bank_schema::*;
 pub use term_bank_schema::*;
-pub use meta_schema::*;
\ No newline at end of file
+pub use meta_schema::*;
+pub use legacy_schema::*;
+pub use bank_reader::*;
+pub use html::*;
+pub use plain_text::*;
\ No newline at end of file
@@ -4,11 +4,11 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Debug)]
 pub struct TagInformation {
-    name: String,
-    category: String,
-    sorting_order: i32,
-    notes: String,
-    popularity_score: i32
+    pub name: String,
+    pub category: String,
+    pub sorting_order: i32,
+    pub notes: String,
+    pub popularity_score: i32
 }
 
 #[derive(Serialize, Deserialize)]
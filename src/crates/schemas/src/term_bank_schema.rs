@@ -823,25 +823,25 @@ where
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TypedImage {
-    path: String,
-    width: Option<i32>,
-    height: Option<i32>,
-    title: Option<String>,
-    alt: Option<String>,
-    description: Option<String>,
-    pixelated: Option<bool>,
+    pub path: String,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub title: Option<String>,
+    pub alt: Option<String>,
+    pub description: Option<String>,
+    pub pixelated: Option<bool>,
     #[serde(rename = "imageRendering")]
-    image_rendering: Option<ImageRendering>,
-    appearance: Option<ImageAppearance>,
-    background: Option<bool>,
-    collapsed: Option<bool>,
-    collapsible: Option<bool>,
+    pub image_rendering: Option<ImageRendering>,
+    pub appearance: Option<ImageAppearance>,
+    pub background: Option<bool>,
+    pub collapsed: Option<bool>,
+    pub collapsible: Option<bool>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct Deinflection {
-    uninflected_term: String,
-    inflection_rules: Vec<String>,
+    pub uninflected_term: String,
+    pub inflection_rules: Vec<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -889,13 +889,11 @@ impl Serialize for TermDefinition {
                     seq.end()
                 }
             },
+            //same shape as the bank, [uninflected term, [rules]], so it can be read back in
             TermDefinition::Inflection(ref inflect) => {
-                let mut seq = serializer.serialize_map(Some(2))?;
-                seq.serialize_entry::<str, String>("uninflected_term", &inflect.uninflected_term)?;
-                seq.serialize_entry::<str, Vec<String>>(
-                    "inflection_rules",
-                    &inflect.inflection_rules,
-                )?;
+                let mut seq = serializer.serialize_seq(Some(2))?;
+                seq.serialize_element(&inflect.uninflected_term)?;
+                seq.serialize_element(&inflect.inflection_rules)?;
                 seq.end()
             }
         }
@@ -923,12 +921,16 @@ impl<'de> Deserialize<'de> for TermDefinition {
                 Ok(TermDefinition::Simple(text.to_string()))
             }
 
-            //structured
-            fn visit_map<A>(self, access: A) -> Result<Self::Value, A::Error>
+            //structured, or an inflection stored by an older version of the library
+            fn visit_map<A>(self, mut access: A) -> Result<Self::Value, A::Error>
             where
                 A: MapAccess<'de>,
             {
-                Ok(TermDefinition::Detailed(deserialize_detailed(access)?))
+                let key = access.next_key::<String>()?;
+                match key.as_deref() {
+                    Some("uninflected_term" | "inflection_rules") => deserialize_stored_inflection(key, access),
+                    _ => Ok(TermDefinition::Detailed(deserialize_detailed(key, access)?)),
+                }
             }
 
             //deinflection
@@ -969,14 +971,40 @@ impl<'de> Deserialize<'de> for TermDefinition {
     }
 }
 
-fn deserialize_detailed<'de, A>(mut access: A) -> Result<DetailedDefinition, A::Error>
+//glossaries used to be stored with inflections as
+//{"uninflected_term": "...", "inflection_rules": [...]}, rows like that are still read
+fn deserialize_stored_inflection<'de, A>(mut key: Option<String>, mut access: A) -> Result<TermDefinition, A::Error>
+where
+    A: MapAccess<'de>,
+{
+    let mut term = None;
+    let mut rules = None;
+    while let Some(name) = key {
+        match name.as_str() {
+            "uninflected_term" => term = Some(access.next_value::<String>()?),
+            "inflection_rules" => rules = Some(access.next_value::<Vec<String>>()?),
+            _ => {
+                access.next_value::<IgnoredAny>()?;
+            }
+        }
+        key = access.next_key::<String>()?;
+    }
+
+    Ok(TermDefinition::Inflection(Deinflection {
+        uninflected_term: term.ok_or_else(|| <A::Error as Error>::missing_field("uninflected_term"))?,
+        inflection_rules: rules.ok_or_else(|| <A::Error as Error>::missing_field("inflection_rules"))?,
+    }))
+}
+
+//key is the first key of the map, which was already read
+fn deserialize_detailed<'de, A>(key: Option<String>, mut access: A) -> Result<DetailedDefinition, A::Error>
 where
     A: MapAccess<'de>,
 {
     const TYPE_FIELDS: &[&str] = &["structured-content", "image", "text"];
 
     //first key should always be "type"
-    if Some("type") != access.next_key::<String>()?.as_deref() {
+    if Some("type") != key.as_deref() {
         return Err(<A::Error as Error>::missing_field("type"));
     }
 
@@ -1095,3 +1123,37 @@ impl Serialize for TermInformation {
 }
 
 pub type DictionaryTermBankV3 = Vec<TermInformation>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inflection_in_either_shape() {
+        let definitions: Vec<TermDefinition> = serde_json::from_str(
+            r#"[["食べる", ["past"]], {"uninflected_term": "食べる", "inflection_rules": ["past"]}]"#,
+        )
+        .unwrap();
+
+        for definition in &definitions {
+            match definition {
+                TermDefinition::Inflection(inflection) => {
+                    assert_eq!(inflection.uninflected_term, "食べる");
+                    assert_eq!(inflection.inflection_rules, vec!["past"]);
+                }
+                other => panic!("expected an inflection, got {:?}", other),
+            }
+        }
+
+        //both get written back out the way banks have them
+        assert_eq!(serde_json::to_string(&definitions).unwrap(), r#"[["食べる",["past"]],["食べる",["past"]]]"#);
+    }
+
+    #[test]
+    fn detailed_definition_still_needs_type() {
+        let content = r#"{"type": "structured-content", "content": "a"}"#;
+        assert!(serde_json::from_str::<TermDefinition>(content).is_ok());
+        let content = r#"{"content": "a", "type": "structured-content"}"#;
+        assert!(serde_json::from_str::<TermDefinition>(content).is_err());
+    }
+}
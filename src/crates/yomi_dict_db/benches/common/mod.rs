@@ -0,0 +1,80 @@
+//generates the large dictionaries the benchmarks run against
+use schemas::{TermDefinition, TermInformation};
+use std::io::Write;
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+
+const TERMS_PER_BANK: usize = 10000;
+
+//small deterministic generator so every run uses the same data
+pub struct Lcg(u64);
+
+impl Lcg {
+    pub fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+
+    pub fn kana(&mut self, min: usize, max: usize) -> String {
+        let length = min + (self.next() as usize) % (max - min + 1);
+        (0..length)
+            .map(|_| char::from_u32(0x3042 + (self.next() as u32) % 80).unwrap())
+            .collect()
+    }
+}
+
+fn generate_term<F>(rng: &mut Lcg, sequence: usize, definition: &mut F) -> TermInformation
+where
+    F: FnMut(&mut Lcg, usize) -> TermDefinition,
+{
+    let reading = rng.kana(2, 6);
+    TermInformation {
+        term: reading.chars().rev().collect(),
+        reading,
+        definition_tags: Some(String::from("n")),
+        deinflectors: String::new(),
+        popularity: (rng.next() % 1000) as i32,
+        definitions: vec![definition(rng, sequence)],
+        sequence_number: sequence as i32,
+        term_tags: String::new(),
+    }
+}
+
+//writes a dictionary of that many terms with one definition each from definition,
+//returns the readings of the terms in the order they were written
+pub fn write_archive<F>(path: &Path, terms: usize, mut definition: F) -> Vec<String>
+where
+    F: FnMut(&mut Lcg, usize) -> TermDefinition,
+{
+    let mut rng = Lcg(0x5eed);
+    let mut readings = Vec::with_capacity(terms);
+
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+    let options = SimpleFileOptions::default();
+
+    zip.start_file("index.json", options).unwrap();
+    zip.write_all(br#"{"title":"Benchmark","revision":"1","format":3,"sequenced":true}"#)
+        .unwrap();
+
+    for (bank, start) in (0..terms).step_by(TERMS_PER_BANK).enumerate() {
+        let end = (start + TERMS_PER_BANK).min(terms);
+        let entries: Vec<TermInformation> = (start..end)
+            .map(|i| generate_term(&mut rng, i, &mut definition))
+            .collect();
+        readings.extend(entries.iter().map(|entry| entry.reading.clone()));
+
+        zip.start_file(format!("term_bank_{}.json", bank + 1), options).unwrap();
+        serde_json::to_writer(&mut zip, &entries).unwrap();
+    }
+
+    zip.finish().unwrap();
+    readings
+}
+
+//how many terms to generate, BENCH_TERMS or 200000
+pub fn bench_terms() -> usize {
+    std::env::var("BENCH_TERMS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(200000)
+}
@@ -0,0 +1,109 @@
+//sequential against parallel import of a large generated dictionary
+//run with: cargo bench -p yomi_dict_db --bench import
+//BENCH_TERMS sets how many terms get generated (default 200000),
+//BENCH_WORKERS how many threads the parallel import uses (default every core)
+mod common;
+
+use common::Lcg;
+use schemas::{
+    DetailedDefinition, StructuredContentNode, StyledElement, StyledElementTag, TagElement, TermDefinition,
+    UnstyledElement, UnstyledElementTag,
+};
+use std::path::Path;
+use std::time::Instant;
+use yomi_dict_db::{ImportOptions, YomitanDatabase};
+
+fn styled(tag: StyledElementTag, content: StructuredContentNode) -> StructuredContentNode {
+    StructuredContentNode::Variant(Box::new(TagElement::Styled(StyledElement {
+        tag,
+        content: Some(content),
+        data: None,
+        style: None,
+        title: None,
+        lang: None,
+    })))
+}
+
+//structured content is what makes parsing expensive, so every term gets a list of
+//senses with ruby in them, like the definitions of the big community dictionaries
+fn generate_definition(rng: &mut Lcg, sequence: usize) -> TermDefinition {
+    let senses = 1 + (rng.next() % 4) as usize;
+    let items = (0..senses)
+        .map(|sense| {
+            let ruby = StructuredContentNode::Variant(Box::new(TagElement::Unstyled(UnstyledElement {
+                tag: UnstyledElementTag::Ruby,
+                content: Some(StructuredContentNode::ChildContent(vec![
+                    StructuredContentNode::Text(rng.kana(1, 3)),
+                    StructuredContentNode::Variant(Box::new(TagElement::Unstyled(UnstyledElement {
+                        tag: UnstyledElementTag::Rt,
+                        content: Some(StructuredContentNode::Text(rng.kana(2, 4))),
+                        data: None,
+                        lang: None,
+                    }))),
+                ])),
+                data: None,
+                lang: None,
+            })));
+            let text = StructuredContentNode::Text(format!(" generated sense {} of {}", sense + 1, sequence));
+            styled(StyledElementTag::Li, StructuredContentNode::ChildContent(vec![ruby, text]))
+        })
+        .collect();
+
+    TermDefinition::Detailed(DetailedDefinition::StructuredContent(styled(
+        StyledElementTag::Ul,
+        StructuredContentNode::ChildContent(items),
+    )))
+}
+
+//every term the import inserted, in the order of their ids
+type TermRow = (i64, String, String, String);
+
+fn time_import(name: &str, archive: &Path, database: &Path, options: ImportOptions) -> Vec<TermRow> {
+    let _ = std::fs::remove_file(database);
+    let db = YomitanDatabase::open_database(database).unwrap();
+
+    let start = Instant::now();
+    let summary = db.import_archive_with_options(archive, options, |_| {}).unwrap();
+    println!(
+        "{:<10} {:>10.2?} ({} terms, {} workers)",
+        name,
+        start.elapsed(),
+        summary.terms,
+        options.workers
+    );
+    drop(db);
+
+    let connection = rusqlite::Connection::open(database).unwrap();
+    let rows = connection
+        .prepare("SELECT id, expression, reading, glossary FROM terms ORDER BY id")
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+        .unwrap()
+        .collect::<Result<Vec<TermRow>, _>>()
+        .unwrap();
+    assert_eq!(rows.len(), summary.terms);
+    drop(connection);
+
+    let _ = std::fs::remove_file(database);
+    rows
+}
+
+fn main() {
+    let terms = common::bench_terms();
+    let mut parallel = ImportOptions::default();
+    if let Some(workers) = std::env::var("BENCH_WORKERS").ok().and_then(|value| value.parse().ok()) {
+        parallel.workers = workers;
+    }
+
+    let directory = std::env::temp_dir();
+    let archive = directory.join("yomi_dict_db_bench_import.zip");
+    let database = directory.join("yomi_dict_db_bench_import.db");
+
+    common::write_archive(&archive, terms, generate_definition);
+
+    let sequential = time_import("sequential", &archive, &database, ImportOptions::sequential());
+    let parallel = time_import("parallel", &archive, &database, parallel);
+    assert!(sequential == parallel, "both imports should insert the same rows in the same order");
+
+    let _ = std::fs::remove_file(&archive);
+}
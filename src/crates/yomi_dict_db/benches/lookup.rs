@@ -0,0 +1,69 @@
+//lookup latency on a large generated dictionary
+//run with: cargo bench -p yomi_dict_db --bench lookup
+//BENCH_TERMS sets how many terms get generated (default 200000)
+mod common;
+
+use schemas::TermDefinition;
+use std::time::Instant;
+use yomi_dict_db::{MatchType, YomitanDatabase};
+
+const QUERIES: usize = 1000;
+
+fn time_lookups(db: &YomitanDatabase, name: &str, queries: &[String], match_type: MatchType) {
+    let mut found = 0;
+    let start = Instant::now();
+    for query in queries {
+        found += db.lookup_terms(query, match_type).unwrap().len();
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{:<8} {:>10.1?} per lookup ({} lookups, {} results)",
+        name,
+        elapsed / queries.len() as u32,
+        queries.len(),
+        found
+    );
+}
+
+fn main() {
+    let terms = common::bench_terms();
+
+    let directory = std::env::temp_dir();
+    let archive = directory.join("yomi_dict_db_bench_lookup.zip");
+    let database = directory.join("yomi_dict_db_bench_lookup.db");
+    let _ = std::fs::remove_file(&database);
+
+    let readings = common::write_archive(&archive, terms, |_, sequence| {
+        TermDefinition::Simple(format!("generated definition {}", sequence))
+    });
+
+    let db = YomitanDatabase::open_database(&database).unwrap();
+    let start = Instant::now();
+    db.import_archive(&archive).unwrap();
+    println!("imported {} terms in {:.2?}", terms, start.elapsed());
+
+    let step = (readings.len() / QUERIES).max(1);
+    let exact: Vec<String> = readings.iter().step_by(step).cloned().collect();
+    let prefix: Vec<String> = exact.iter().map(|reading| reading.chars().take(2).collect()).collect();
+    let suffix: Vec<String> = exact
+        .iter()
+        .map(|reading| {
+            let chars: Vec<char> = reading.chars().collect();
+            chars[chars.len() - 2..].iter().collect()
+        })
+        .collect();
+
+    //warm up the page cache so the first measurement isn't an outlier
+    for query in exact.iter().take(10) {
+        db.lookup_terms(query, MatchType::Exact).unwrap();
+    }
+
+    time_lookups(&db, "exact", &exact, MatchType::Exact);
+    time_lookups(&db, "prefix", &prefix, MatchType::Prefix);
+    time_lookups(&db, "suffix", &suffix, MatchType::Suffix);
+
+    drop(db);
+    let _ = std::fs::remove_file(&database);
+    let _ = std::fs::remove_file(&archive);
+}
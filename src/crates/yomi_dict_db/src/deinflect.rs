@@ -0,0 +1,577 @@
+use crate::{DatabaseTerm, MatchType, YomitanDatabase};
+use rusqlite::Error;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::BitOr;
+use std::sync::OnceLock;
+use wana_kana::IsJapaneseChar;
+
+//word classes a deinflected form can belong to. the first six match the rule
+//tags dictionaries put on terms, the rest are forms in the middle of a chain
+//(like the て in 食べている) that still need another rule before they're a word
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Conditions(u32);
+
+impl Conditions {
+    //on a form this means it's the text as given and can be any kind of word.
+    //on a rule it means the rule only applies to the text as given
+    pub const NONE: Conditions = Conditions(0);
+    pub const V1: Conditions = Conditions(1);
+    pub const V5: Conditions = Conditions(1 << 1);
+    pub const VK: Conditions = Conditions(1 << 2);
+    pub const VS: Conditions = Conditions(1 << 3);
+    pub const VZ: Conditions = Conditions(1 << 4);
+    pub const ADJ_I: Conditions = Conditions(1 << 5);
+    pub const TE: Conditions = Conditions(1 << 6);
+    pub const MASU: Conditions = Conditions(1 << 7);
+
+    const VERB: Conditions = Conditions(Self::V1.0 | Self::V5.0 | Self::VK.0 | Self::VS.0 | Self::VZ.0);
+
+    //reads the space separated rules of a term bank entry, like "v5 vt".
+    //the specific godan and ichidan classes (v5k, v1-s ...) count as v5 and v1
+    pub fn from_rules(rules: &str) -> Conditions {
+        rules
+            .split_whitespace()
+            .map(|rule| match rule {
+                "adj-i" => Conditions::ADJ_I,
+                "vk" => Conditions::VK,
+                "vz" => Conditions::VZ,
+                _ if rule.starts_with("v1") => Conditions::V1,
+                _ if rule.starts_with("v5") => Conditions::V5,
+                _ if rule.starts_with("vs") => Conditions::VS,
+                _ => Conditions::NONE,
+            })
+            .fold(Conditions::NONE, |all, conditions| all | conditions)
+    }
+
+    pub fn intersects(self, other: Conditions) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl BitOr for Conditions {
+    type Output = Conditions;
+
+    fn bitor(self, other: Conditions) -> Conditions {
+        Conditions(self.0 | other.0)
+    }
+}
+
+//a candidate dictionary form of some inflected text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeinflectedForm {
+    pub text: String,
+    pub conditions: Conditions,
+    //the transforms that were undone, starting from the dictionary form,
+    //so 食べなかった gives ["negative", "past"]
+    pub reasons: Vec<&'static str>,
+}
+
+impl DeinflectedForm {
+    //whether a term with these rules can be the word this form came from
+    pub fn matches_rules(&self, rules: &str) -> bool {
+        self.conditions == Conditions::NONE || self.conditions.intersects(Conditions::from_rules(rules))
+    }
+}
+
+struct Rule {
+    reason: &'static str,
+    kana_in: String,
+    kana_out: String,
+    rules_in: Conditions,
+    rules_out: Conditions,
+}
+
+//the stems of one row of godan verbs, named after the vowel they end in
+struct GodanRow {
+    ending: &'static str,
+    i: &'static str,
+    a: &'static str,
+    e: &'static str,
+    o: &'static str,
+    te: &'static str,
+}
+
+const fn row(
+    ending: &'static str,
+    i: &'static str,
+    a: &'static str,
+    e: &'static str,
+    o: &'static str,
+    te: &'static str,
+) -> GodanRow {
+    GodanRow { ending, i, a, e, o, te }
+}
+
+const GODAN_ROWS: [GodanRow; 9] = [
+    row("う", "い", "わ", "え", "お", "って"),
+    row("く", "き", "か", "け", "こ", "いて"),
+    row("ぐ", "ぎ", "が", "げ", "ご", "いで"),
+    row("す", "し", "さ", "せ", "そ", "して"),
+    row("つ", "ち", "た", "て", "と", "って"),
+    row("ぬ", "に", "な", "ね", "の", "んで"),
+    row("ぶ", "び", "ば", "べ", "ぼ", "んで"),
+    row("む", "み", "ま", "め", "も", "んで"),
+    row("る", "り", "ら", "れ", "ろ", "って"),
+];
+
+//行く is the one godan verb with an irregular て form
+const IKU_ROW: GodanRow = row("く", "き", "か", "け", "こ", "って");
+
+//an inflection and the endings it has for each class of word.
+//godan endings are built from the row, する forms also give the ずる forms
+//and くる forms also give the ones written with 来
+struct Inflection {
+    reason: &'static str,
+    rules_in: Conditions,
+    ichidan: &'static [&'static str],
+    godan: Option<fn(&GodanRow) -> String>,
+    kuru: &'static [&'static str],
+    suru: &'static [&'static str],
+    adjective: &'static [&'static str],
+}
+
+const fn inflection(reason: &'static str, rules_in: Conditions) -> Inflection {
+    Inflection {
+        reason,
+        rules_in,
+        ichidan: &[],
+        godan: None,
+        kuru: &[],
+        suru: &[],
+        adjective: &[],
+    }
+}
+
+fn te(row: &GodanRow) -> String {
+    row.te.to_string()
+}
+
+fn ta(row: &GodanRow) -> String {
+    past_of(row.te)
+}
+
+//って to った, いで to いだ and so on
+fn past_of(te_form: &str) -> String {
+    let mut past = te_form.to_string();
+    match past.pop() {
+        Some('で') => past.push('だ'),
+        _ => past.push('た'),
+    }
+    past
+}
+
+//て to ちゃう and で to じゃう, with the given ending in place of う
+fn contracted(te_form: &str, ending: &str) -> String {
+    let mut stem = te_form.to_string();
+    match stem.pop() {
+        Some('で') => stem + "じゃ" + ending,
+        _ => stem + "ちゃ" + ending,
+    }
+}
+
+const INFLECTIONS: &[Inflection] = &[
+    Inflection {
+        ichidan: &["ない"],
+        godan: Some(|row| format!("{}ない", row.a)),
+        kuru: &["こない"],
+        suru: &["しない"],
+        adjective: &["くない"],
+        ..inflection("negative", Conditions::ADJ_I)
+    },
+    Inflection {
+        ichidan: &["た"],
+        godan: Some(ta),
+        kuru: &["きた"],
+        suru: &["した"],
+        adjective: &["かった"],
+        ..inflection("past", Conditions::NONE)
+    },
+    Inflection {
+        ichidan: &["て"],
+        godan: Some(te),
+        kuru: &["きて"],
+        suru: &["して"],
+        adjective: &["くて"],
+        ..inflection("-te", Conditions::TE)
+    },
+    Inflection {
+        ichidan: &["たら"],
+        godan: Some(|row| ta(row) + "ら"),
+        kuru: &["きたら"],
+        suru: &["したら"],
+        adjective: &["かったら"],
+        ..inflection("-tara", Conditions::NONE)
+    },
+    Inflection {
+        ichidan: &["たり"],
+        godan: Some(|row| ta(row) + "り"),
+        kuru: &["きたり"],
+        suru: &["したり"],
+        adjective: &["かったり"],
+        ..inflection("-tari", Conditions::NONE)
+    },
+    Inflection {
+        ichidan: &["れば"],
+        godan: Some(|row| format!("{}ば", row.e)),
+        kuru: &["くれば"],
+        suru: &["すれば"],
+        adjective: &["ければ"],
+        ..inflection("-ba", Conditions::NONE)
+    },
+    Inflection {
+        ichidan: &["ず"],
+        godan: Some(|row| format!("{}ず", row.a)),
+        kuru: &["こず"],
+        suru: &["せず"],
+        ..inflection("-zu", Conditions::NONE)
+    },
+    Inflection {
+        ichidan: &["たい"],
+        godan: Some(|row| format!("{}たい", row.i)),
+        kuru: &["きたい"],
+        suru: &["したい"],
+        ..inflection("-tai", Conditions::ADJ_I)
+    },
+    Inflection {
+        ichidan: &["ます"],
+        godan: Some(|row| format!("{}ます", row.i)),
+        kuru: &["きます"],
+        suru: &["します"],
+        ..inflection("polite", Conditions::MASU)
+    },
+    Inflection {
+        ichidan: &["そう"],
+        godan: Some(|row| format!("{}そう", row.i)),
+        kuru: &["きそう"],
+        suru: &["しそう"],
+        adjective: &["そう"],
+        ..inflection("-sou", Conditions::NONE)
+    },
+    Inflection {
+        ichidan: &["すぎる"],
+        godan: Some(|row| format!("{}すぎる", row.i)),
+        kuru: &["きすぎる"],
+        suru: &["しすぎる"],
+        adjective: &["すぎる"],
+        ..inflection("-sugiru", Conditions::V1)
+    },
+    Inflection {
+        ichidan: &["なさい"],
+        godan: Some(|row| format!("{}なさい", row.i)),
+        kuru: &["きなさい"],
+        suru: &["しなさい"],
+        ..inflection("-nasai", Conditions::NONE)
+    },
+    Inflection {
+        ichidan: &["よう"],
+        godan: Some(|row| format!("{}う", row.o)),
+        kuru: &["こよう"],
+        suru: &["しよう"],
+        adjective: &["かろう"],
+        ..inflection("volitional", Conditions::NONE)
+    },
+    Inflection {
+        ichidan: &["ろ", "よ"],
+        godan: Some(|row| row.e.to_string()),
+        kuru: &["こい"],
+        suru: &["しろ", "せよ"],
+        ..inflection("imperative", Conditions::NONE)
+    },
+    Inflection {
+        ichidan: &["させる"],
+        godan: Some(|row| format!("{}せる", row.a)),
+        kuru: &["こさせる"],
+        suru: &["させる"],
+        ..inflection("causative", Conditions::V1)
+    },
+    Inflection {
+        godan: Some(|row| format!("{}れる", row.a)),
+        suru: &["される"],
+        ..inflection("passive", Conditions::V1)
+    },
+    Inflection {
+        //ら抜き言葉, 食べれる for 食べられる
+        ichidan: &["れる"],
+        godan: Some(|row| format!("{}る", row.e)),
+        kuru: &["これる"],
+        ..inflection("potential", Conditions::V1)
+    },
+    Inflection {
+        ichidan: &["られる"],
+        kuru: &["こられる"],
+        ..inflection("potential or passive", Conditions::V1)
+    },
+    Inflection {
+        //the short form, 書かされる for 書かせられる
+        godan: Some(|row| match row.ending {
+            "す" => String::new(),
+            _ => format!("{}される", row.a),
+        }),
+        ..inflection("causative passive", Conditions::V1)
+    },
+    Inflection {
+        ichidan: &["ちゃう"],
+        godan: Some(|row| contracted(row.te, "う")),
+        kuru: &["きちゃう"],
+        suru: &["しちゃう"],
+        ..inflection("-chau", Conditions::V5)
+    },
+    Inflection {
+        ichidan: &["ちまう"],
+        godan: Some(|row| contracted(row.te, "まう")),
+        kuru: &["きちまう"],
+        suru: &["しちまう"],
+        ..inflection("-chimau", Conditions::V5)
+    },
+    Inflection {
+        adjective: &["く"],
+        ..inflection("adv", Conditions::NONE)
+    },
+    Inflection {
+        adjective: &["さ"],
+        ..inflection("noun", Conditions::NONE)
+    },
+];
+
+//rules that don't depend on the class of word, mostly auxiliaries that
+//attach to a form that still has to be deinflected itself
+const AUXILIARIES: &[(&str, &str, &str, Conditions, Conditions)] = &[
+    ("polite past", "ました", "ます", Conditions::NONE, Conditions::MASU),
+    ("polite negative", "ません", "ます", Conditions::NONE, Conditions::MASU),
+    ("polite past negative", "ませんでした", "ます", Conditions::NONE, Conditions::MASU),
+    ("polite volitional", "ましょう", "ます", Conditions::NONE, Conditions::MASU),
+    ("polite te", "まして", "ます", Conditions::NONE, Conditions::MASU),
+    ("-teiru", "ている", "て", Conditions::V1, Conditions::TE),
+    ("-teiru", "でいる", "で", Conditions::V1, Conditions::TE),
+    ("-teiru", "てる", "て", Conditions::V1, Conditions::TE),
+    ("-teiru", "でる", "で", Conditions::V1, Conditions::TE),
+    ("-shimau", "てしまう", "て", Conditions::V5, Conditions::TE),
+    ("-shimau", "でしまう", "で", Conditions::V5, Conditions::TE),
+    ("-toku", "ておく", "て", Conditions::V5, Conditions::TE),
+    ("-toku", "でおく", "で", Conditions::V5, Conditions::TE),
+    ("-toku", "とく", "て", Conditions::V5, Conditions::TE),
+    ("-toku", "どく", "で", Conditions::V5, Conditions::TE),
+    ("imperative negative", "な", "", Conditions::NONE, Conditions::VERB),
+];
+
+//the く, す and ず of くる, する and ずる take the place of the first kana
+fn swap_first(form: &str, first: &str) -> String {
+    let mut chars = form.chars();
+    chars.next();
+    format!("{}{}", first, chars.as_str())
+}
+
+fn build_rules() -> Vec<Rule> {
+    let mut rules = Vec::new();
+
+    for inflection in INFLECTIONS {
+        let mut push = |kana_in: String, kana_out: &str, rules_out: Conditions| {
+            if !kana_in.is_empty() {
+                rules.push(Rule {
+                    reason: inflection.reason,
+                    kana_in,
+                    kana_out: kana_out.to_string(),
+                    rules_in: inflection.rules_in,
+                    rules_out,
+                });
+            }
+        };
+
+        for ending in inflection.ichidan {
+            push(ending.to_string(), "る", Conditions::V1);
+        }
+        if let Some(godan) = inflection.godan {
+            for row in &GODAN_ROWS {
+                push(godan(row), row.ending, Conditions::V5);
+            }
+
+            //only the forms built on the て form differ from any other く verb
+            let regular = godan(&GODAN_ROWS[1]);
+            let iku = godan(&IKU_ROW);
+            if !regular.is_empty() && iku != regular {
+                push(format!("い{}", iku), "いく", Conditions::V5);
+                push(format!("行{}", iku), "行く", Conditions::V5);
+            }
+        }
+        for form in inflection.kuru {
+            push(form.to_string(), "くる", Conditions::VK);
+            push(swap_first(form, "来"), "来る", Conditions::VK);
+        }
+        for form in inflection.suru {
+            push(form.to_string(), "する", Conditions::VS);
+
+            let zuru = match form.chars().next() {
+                Some('し') => swap_first(form, "じ"),
+                Some('せ') => swap_first(form, "ぜ"),
+                Some('さ') => swap_first(form, "ざ"),
+                Some('す') => swap_first(form, "ず"),
+                _ => continue,
+            };
+            push(zuru, "ずる", Conditions::VZ);
+        }
+        for ending in inflection.adjective {
+            push(ending.to_string(), "い", Conditions::ADJ_I);
+        }
+    }
+
+    for (reason, kana_in, kana_out, rules_in, rules_out) in AUXILIARIES {
+        rules.push(Rule {
+            reason,
+            kana_in: kana_in.to_string(),
+            kana_out: kana_out.to_string(),
+            rules_in: *rules_in,
+            rules_out: *rules_out,
+        });
+    }
+
+    rules
+}
+
+fn rules() -> &'static [Rule] {
+    static RULES: OnceLock<Vec<Rule>> = OnceLock::new();
+    RULES.get_or_init(build_rules)
+}
+
+//every form the text could have been inflected from, starting with the text itself.
+//forms are in the order they were found, so ones with fewer transforms come first.
+//most of them won't be real words, which is sorted out by checking the
+//conditions against the rules of the terms they match in a dictionary
+pub fn deinflect(text: &str) -> Vec<DeinflectedForm> {
+    let mut forms = vec![DeinflectedForm {
+        text: text.to_string(),
+        conditions: Conditions::NONE,
+        reasons: Vec::new(),
+    }];
+    let mut seen = HashSet::from([(text.to_string(), Conditions::NONE)]);
+    let mut queue = VecDeque::from([0]);
+
+    while let Some(index) = queue.pop_front() {
+        //every inflection ends in hiragana, so there's nothing to undo otherwise
+        if !forms[index].text.chars().last().is_some_and(|c| c.is_hiragana()) {
+            continue;
+        }
+
+        for rule in rules() {
+            let form = &forms[index];
+            if form.conditions != Conditions::NONE && !form.conditions.intersects(rule.rules_in) {
+                continue;
+            }
+
+            let Some(stem) = form.text.strip_suffix(rule.kana_in.as_str()) else {
+                continue;
+            };
+            let text = format!("{}{}", stem, rule.kana_out);
+            if text.is_empty() || !seen.insert((text.clone(), rule.rules_out)) {
+                continue;
+            }
+
+            let mut reasons = Vec::with_capacity(form.reasons.len() + 1);
+            reasons.push(rule.reason);
+            reasons.extend_from_slice(&form.reasons);
+
+            forms.push(DeinflectedForm {
+                text,
+                conditions: rule.rules_out,
+                reasons,
+            });
+            queue.push_back(forms.len() - 1);
+        }
+    }
+
+    forms
+}
+
+//a term found by looking up one of the deinflected forms of some text
+#[derive(Debug)]
+pub struct DeinflectedTerm {
+    pub form: DeinflectedForm,
+    pub term: DatabaseTerm,
+}
+
+impl YomitanDatabase {
+    //looks up every deinflected form of the text by expression and reading,
+    //keeping the terms whose rules fit how the form was deinflected.
+    //a term that several forms lead to is only returned for the first one
+    pub fn lookup_deinflected(&self, text: &str) -> Result<Vec<DeinflectedTerm>, Error> {
+        let mut found = Vec::new();
+        let mut seen_ids = HashSet::new();
+        //several forms can have the same text with different conditions,
+        //terms are taken out of here as they get matched
+        let mut cache: HashMap<String, Vec<Option<DatabaseTerm>>> = HashMap::new();
+
+        for form in deinflect(text) {
+            if !cache.contains_key(&form.text) {
+                let terms = self.lookup_terms(&form.text, MatchType::Exact)?;
+                cache.insert(form.text.clone(), terms.into_iter().map(Some).collect());
+            }
+
+            for slot in cache.get_mut(&form.text).into_iter().flatten() {
+                let matches = slot
+                    .as_ref()
+                    .is_some_and(|term| form.matches_rules(&term.term.deinflectors) && !seen_ids.contains(&term.id));
+                if !matches {
+                    continue;
+                }
+
+                if let Some(term) = slot.take() {
+                    seen_ids.insert(term.id);
+                    found.push(DeinflectedTerm {
+                        form: form.clone(),
+                        term,
+                    });
+                }
+            }
+        }
+
+        Ok(found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //whether the text deinflects to a word with the rules a dictionary would give it
+    fn deinflects_to(text: &str, dictionary_form: &str, rules: &str) -> bool {
+        deinflect(text)
+            .iter()
+            .any(|form| form.text == dictionary_form && form.matches_rules(rules))
+    }
+
+    #[test]
+    fn chained_inflections() {
+        assert!(deinflects_to("食べさせられなかった", "食べる", "v1"));
+        assert!(deinflects_to("書かされた", "書く", "v5"));
+    }
+
+    #[test]
+    fn irregular_verbs() {
+        assert!(deinflects_to("行った", "行く", "v5"));
+        assert!(deinflects_to("来なかった", "来る", "vk"));
+        assert!(deinflects_to("しませんでした", "する", "vs"));
+    }
+
+    #[test]
+    fn adjectives() {
+        assert!(deinflects_to("高くない", "高い", "adj-i"));
+    }
+
+    #[test]
+    fn imperative_negative() {
+        assert!(deinflects_to("食べるな", "食べる", "v1"));
+    }
+
+    #[test]
+    fn text_as_given_comes_first() {
+        let forms = deinflect("食べた");
+        assert_eq!(forms[0].text, "食べた");
+        assert_eq!(forms[0].conditions, Conditions::NONE);
+    }
+
+    #[test]
+    fn wrong_word_class_is_rejected() {
+        //食べた is the past of an ichidan verb, a godan 食べる doesn't exist
+        assert!(deinflects_to("食べた", "食べる", "v1"));
+        assert!(!deinflects_to("食べた", "食べる", "v5"));
+    }
+}
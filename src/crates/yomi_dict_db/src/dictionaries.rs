@@ -0,0 +1,202 @@
+use crate::YomitanDatabase;
+use chrono::{DateTime, Local};
+use rusqlite::{Error, OptionalExtension, Row};
+use schemas::FrequencyMode;
+use std::str::FromStr;
+
+//every table that keeps rows for a dictionary in its "dictionary" column
+pub(crate) const DICTIONARY_TABLES: [&str; 6] = ["terms", "termMeta", "kanji", "kanjiMeta", "tagMeta", "media"];
+
+//number of rows a dictionary has in each table
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DictionaryCounts {
+    pub terms: usize,
+    pub term_meta: usize,
+    pub kanji: usize,
+    pub kanji_meta: usize,
+    pub tags: usize,
+    pub media: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct DictionaryInfo {
+    pub id: i64,
+    pub title: String,
+    pub revision: String,
+    pub version: Option<i64>,
+    pub import_date: Option<DateTime<Local>>,
+    pub prefix_wildcards_supported: bool,
+    pub sequenced: bool,
+    pub enabled: bool,
+    //lower priorities are shown first in lookups
+    pub priority: i64,
+    pub counts: DictionaryCounts,
+    //iso language codes, like "ja" and "en"
+    pub source_language: Option<String>,
+    pub target_language: Option<String>,
+    //only set for frequency dictionaries
+    pub frequency_mode: Option<FrequencyMode>,
+    pub minimum_yomitan_version: Option<String>,
+}
+
+//css for a dictionary's entries. renderers should apply styles and then user_styles,
+//both scoped to the dictionary so they don't leak into other dictionaries' entries
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DictionaryStyles {
+    //the styles.css from the dictionary archive
+    pub styles: Option<String>,
+    pub user_styles: Option<String>,
+}
+
+fn dictionary_from_row(row: &Row) -> Result<DictionaryInfo, Error> {
+    Ok(DictionaryInfo {
+        id: row.get("id")?,
+        title: row.get("title")?,
+        revision: row.get("revision")?,
+        version: row.get("version")?,
+        import_date: row.get("import_date")?,
+        prefix_wildcards_supported: row.get::<_, Option<bool>>("prefix_wildcards_supported")?.unwrap_or_default(),
+        sequenced: row.get::<_, Option<bool>>("sequenced")?.unwrap_or_default(),
+        enabled: row.get::<_, Option<bool>>("enabled")?.unwrap_or(true),
+        priority: row.get::<_, Option<i64>>("priority")?.unwrap_or_default(),
+        counts: DictionaryCounts::default(),
+        source_language: row.get("source_language")?,
+        target_language: row.get("target_language")?,
+        frequency_mode: row
+            .get::<_, Option<String>>("frequency_mode")?
+            .and_then(|mode| FrequencyMode::from_str(&mode).ok()),
+        minimum_yomitan_version: row.get("minimum_yomitan_version")?,
+    })
+}
+
+impl YomitanDatabase {
+    //every installed dictionary in priority order, along with how many rows each one has
+    pub fn list_dictionaries(&self) -> Result<Vec<DictionaryInfo>, Error> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT id, title, revision, version, import_date, prefix_wildcards_supported, sequenced, enabled, priority, \
+             source_language, target_language, frequency_mode, minimum_yomitan_version FROM dictionaries ORDER BY priority, id",
+        )?;
+        let mut dictionaries = statement
+            .query_map([], dictionary_from_row)?
+            .collect::<Result<Vec<DictionaryInfo>, Error>>()?;
+
+        for dictionary in dictionaries.iter_mut() {
+            dictionary.counts = self.dictionary_counts(&dictionary.title)?;
+        }
+
+        Ok(dictionaries)
+    }
+
+    pub fn dictionary_counts(&self, title: &str) -> Result<DictionaryCounts, Error> {
+        let count = |table: &str| -> Result<usize, Error> {
+            self.connection
+                .prepare_cached(&format!("SELECT COUNT(*) FROM {} WHERE dictionary = ?1", table))?
+                .query_row([title], |row| row.get(0))
+        };
+
+        Ok(DictionaryCounts {
+            terms: count("terms")?,
+            term_meta: count("termMeta")?,
+            kanji: count("kanji")?,
+            kanji_meta: count("kanjiMeta")?,
+            tags: count("tagMeta")?,
+            media: count("media")?,
+        })
+    }
+
+    //removes a dictionary and everything that was imported with it.
+    //returns false if there was no dictionary with that title
+    pub fn delete_dictionary(&self, title: &str) -> Result<bool, Error> {
+        self.with_savepoint("delete_dictionary", || {
+            //termsFts has no dictionary column, so it goes first while the terms are still there
+            self.connection.execute(
+                "DELETE FROM termsFts WHERE rowid IN (SELECT id FROM terms WHERE dictionary = ?1)",
+                [title],
+            )?;
+
+            for table in DICTIONARY_TABLES {
+                self.connection
+                    .execute(&format!("DELETE FROM {} WHERE dictionary = ?1", table), [title])?;
+            }
+
+            let deleted = self.connection.execute("DELETE FROM dictionaries WHERE title = ?1", [title])?;
+            Ok(deleted > 0)
+        })
+    }
+
+    //returns false if there is no dictionary called old_title,
+    //or if new_title is already taken by another dictionary
+    pub fn rename_dictionary(&self, old_title: &str, new_title: &str) -> Result<bool, Error> {
+        if old_title == new_title {
+            return self.dictionary_exists(old_title);
+        }
+        if !self.dictionary_exists(old_title)? || self.dictionary_exists(new_title)? {
+            return Ok(false);
+        }
+
+        self.with_savepoint("rename_dictionary", || {
+            for table in DICTIONARY_TABLES {
+                self.connection.execute(
+                    &format!("UPDATE {} SET dictionary = ?2 WHERE dictionary = ?1", table),
+                    [old_title, new_title],
+                )?;
+            }
+
+            self.connection
+                .execute("UPDATE dictionaries SET title = ?2 WHERE title = ?1", [old_title, new_title])?;
+            Ok(true)
+        })
+    }
+
+    //disabled dictionaries stay installed but are skipped by lookups
+    pub fn set_dictionary_enabled(&self, title: &str, enabled: bool) -> Result<bool, Error> {
+        let updated = self
+            .connection
+            .execute("UPDATE dictionaries SET enabled = ?2 WHERE title = ?1", (title, enabled))?;
+        Ok(updated > 0)
+    }
+
+    //sets the order lookups list dictionaries in, first title is shown first.
+    //dictionaries that aren't in the list keep their relative order and go after the listed ones
+    pub fn set_dictionary_order(&self, titles: &[&str]) -> Result<(), Error> {
+        let mut order: Vec<String> = titles.iter().map(|title| title.to_string()).collect();
+
+        let mut statement = self.connection.prepare_cached("SELECT title FROM dictionaries ORDER BY priority, id")?;
+        for title in statement.query_map([], |row| row.get::<_, String>(0))? {
+            let title = title?;
+            if !order.contains(&title) {
+                order.push(title);
+            }
+        }
+
+        self.with_savepoint("set_dictionary_order", || {
+            for (priority, title) in order.iter().enumerate() {
+                self.connection
+                    .execute("UPDATE dictionaries SET priority = ?2 WHERE title = ?1", (title, priority))?;
+            }
+            Ok(())
+        })
+    }
+
+    //None if there is no dictionary with that title
+    pub fn dictionary_styles(&self, title: &str) -> Result<Option<DictionaryStyles>, Error> {
+        self.connection
+            .prepare_cached("SELECT styles, user_styles FROM dictionaries WHERE title = ?1")?
+            .query_row([title], |row| {
+                Ok(DictionaryStyles {
+                    styles: row.get("styles")?,
+                    user_styles: row.get("user_styles")?,
+                })
+            })
+            .optional()
+    }
+
+    //replaces the user's css overrides for a dictionary, None removes them.
+    //returns false if there was no dictionary with that title
+    pub fn set_dictionary_user_styles(&self, title: &str, user_styles: Option<&str>) -> Result<bool, Error> {
+        let updated = self
+            .connection
+            .execute("UPDATE dictionaries SET user_styles = ?2 WHERE title = ?1", (title, user_styles))?;
+        Ok(updated > 0)
+    }
+}
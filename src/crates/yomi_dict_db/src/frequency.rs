@@ -0,0 +1,81 @@
+use crate::lookup::term_meta_from_row;
+use crate::{DatabaseTerm, YomitanDatabase};
+use rusqlite::{Error, OptionalExtension};
+use schemas::FrequencyMode;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+impl YomitanDatabase {
+    //how a dictionary's frequencies should be read. dictionaries whose index doesn't
+    //say are treated as rank based, since that's what most frequency dictionaries are
+    pub fn frequency_mode(&self, dictionary: &str) -> Result<FrequencyMode, Error> {
+        let mode: Option<String> = self
+            .connection
+            .prepare_cached("SELECT frequency_mode FROM dictionaries WHERE title = ?1")?
+            .query_row([dictionary], |row| row.get(0))
+            .optional()?
+            .flatten();
+
+        Ok(mode
+            .and_then(|mode| FrequencyMode::from_str(&mode).ok())
+            .unwrap_or(FrequencyMode::RankBased))
+    }
+
+    //the value a frequency dictionary has for a term, frequencies for other readings are skipped.
+    //if the dictionary lists more than one, the most common of them is used
+    pub fn term_frequency(&self, dictionary: &str, expression: &str, reading: &str) -> Result<Option<i64>, Error> {
+        let mode = self.frequency_mode(dictionary)?;
+
+        let mut statement = self.connection.prepare_cached(
+            "SELECT id, dictionary, data, expression, mode FROM termMeta \
+             WHERE dictionary = ?1 AND expression = ?2 AND mode = 'freq'",
+        )?;
+        let rows = statement.query_map((dictionary, expression), term_meta_from_row)?;
+
+        let mut best: Option<i64> = None;
+        for row in rows {
+            let (_, meta) = row?;
+            if meta.reading().is_some_and(|meta_reading| meta_reading != reading) {
+                continue;
+            }
+
+            let Some(value) = meta.frequency().and_then(|frequency| frequency.value()) else {
+                continue;
+            };
+            best = Some(match (best, mode) {
+                (None, _) => value,
+                (Some(best), FrequencyMode::RankBased) => best.min(value),
+                (Some(best), FrequencyMode::OccurrenceBased) => best.max(value),
+            });
+        }
+
+        Ok(best)
+    }
+
+    //sorts terms from the most to the least common according to a frequency dictionary.
+    //terms it has no frequency for go last, the sort is stable so ties keep their order
+    pub fn sort_terms_by_frequency(&self, terms: &mut Vec<DatabaseTerm>, dictionary: &str) -> Result<(), Error> {
+        let mode = self.frequency_mode(dictionary)?;
+
+        let mut keyed = Vec::with_capacity(terms.len());
+        for (index, term) in terms.iter().enumerate() {
+            let frequency = self.term_frequency(dictionary, &term.term.term, &term.term.reading)?;
+            keyed.push((frequency, index));
+        }
+
+        keyed.sort_by(|(a, _), (b, _)| match (a, b) {
+            (Some(a), Some(b)) => match mode {
+                FrequencyMode::RankBased => a.cmp(b),
+                FrequencyMode::OccurrenceBased => b.cmp(a),
+            },
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        });
+
+        let mut unsorted: Vec<Option<DatabaseTerm>> = terms.drain(..).map(Some).collect();
+        terms.extend(keyed.into_iter().filter_map(|(_, index)| unsorted[index].take()));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,592 @@
+use crate::{collect_image_paths, migrations, YomitanDatabase};
+use schemas::*;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::sync::mpsc::{sync_channel, SyncSender};
+use zip::ZipArchive;
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io(std::io::Error),
+    Zip(zip::result::ZipError),
+    Database(rusqlite::Error),
+    //json that failed to deserialize, path is the location inside of the file
+    Json {
+        file: String,
+        path: String,
+        source: serde_json::Error,
+    },
+    //an entry of a bank that failed to parse
+    Bank {
+        file: String,
+        source: BankError,
+    },
+    MissingIndex,
+    AlreadyImported(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(err) => write!(f, "io error: {}", err),
+            ImportError::Zip(err) => write!(f, "zip error: {}", err),
+            ImportError::Database(err) => write!(f, "database error: {}", err),
+            ImportError::Json { file, path, source } => {
+                write!(f, "failed to parse {} at {}: {}", file, path, source)
+            }
+            ImportError::Bank { file, source } => match source.path.is_empty() {
+                true => write!(f, "failed to parse {}: {}", file, source.message()),
+                false => write!(f, "failed to parse {} at {}: {}", file, source.path, source.message()),
+            },
+            ImportError::MissingIndex => write!(f, "archive does not contain an index.json"),
+            ImportError::AlreadyImported(title) => {
+                write!(f, "dictionary \"{}\" is already imported", title)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImportError::Io(err) => Some(err),
+            ImportError::Zip(err) => Some(err),
+            ImportError::Database(err) => Some(err),
+            ImportError::Json { source, .. } => Some(source),
+            ImportError::Bank { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ImportError {
+    fn from(err: std::io::Error) -> Self {
+        ImportError::Io(err)
+    }
+}
+
+impl From<zip::result::ZipError> for ImportError {
+    fn from(err: zip::result::ZipError) -> Self {
+        ImportError::Zip(err)
+    }
+}
+
+impl From<rusqlite::Error> for ImportError {
+    fn from(err: rusqlite::Error) -> Self {
+        ImportError::Database(err)
+    }
+}
+
+//the kinds of json banks that can be in a dictionary archive,
+//in the order they get imported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BankKind {
+    Tag,
+    Term,
+    TermMeta,
+    Kanji,
+    KanjiMeta,
+}
+
+impl BankKind {
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            BankKind::Tag => "tag_bank",
+            BankKind::Term => "term_bank",
+            BankKind::TermMeta => "term_meta_bank",
+            BankKind::Kanji => "kanji_bank",
+            BankKind::KanjiMeta => "kanji_meta_bank",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BankFile {
+    pub kind: BankKind,
+    pub number: u32,
+    pub name: String,
+}
+
+impl BankFile {
+    //parses names like "term_bank_12.json", anything else is not a bank
+    pub fn from_name(name: &str) -> Option<BankFile> {
+        const KINDS: [BankKind; 5] = [
+            BankKind::Tag,
+            BankKind::Term,
+            BankKind::TermMeta,
+            BankKind::Kanji,
+            BankKind::KanjiMeta,
+        ];
+
+        let stem = name.strip_suffix(".json")?;
+        let (prefix, number) = stem.rsplit_once('_')?;
+        let kind = KINDS.into_iter().find(|kind| kind.prefix() == prefix)?;
+        let number = number.parse::<u32>().ok()?;
+
+        Some(BankFile {
+            kind,
+            number,
+            name: name.to_string(),
+        })
+    }
+}
+
+//number of rows imported from each kind of bank
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub title: String,
+    pub terms: usize,
+    pub term_meta: usize,
+    pub kanji: usize,
+    pub kanji_meta: usize,
+    pub tags: usize,
+    pub media: usize,
+}
+
+//finds every bank in the archive, sorted by kind and then by bank number
+pub fn list_banks<R: Read + std::io::Seek>(archive: &ZipArchive<R>) -> Vec<BankFile> {
+    let mut banks: Vec<BankFile> = archive.file_names().filter_map(BankFile::from_name).collect();
+    banks.sort_by_key(|bank| (bank.kind, bank.number));
+    banks
+}
+
+pub(crate) fn read_json<T, R>(archive: &mut ZipArchive<R>, name: &str) -> Result<T, ImportError>
+where
+    T: DeserializeOwned,
+    R: Read + std::io::Seek,
+{
+    let file = archive.by_name(name)?;
+    let reader = BufReader::new(file);
+    let jd = &mut serde_json::Deserializer::from_reader(reader);
+
+    serde_path_to_error::deserialize(jd).map_err(|err| ImportError::Json {
+        file: name.to_string(),
+        path: err.path().to_string(),
+        source: err.into_inner(),
+    })
+}
+
+//how many entries of a bank are parsed before they're handed to the writer
+const CHUNK_SIZE: usize = 1024;
+
+//a rough size of one bank entry in its json, used to guess how many rows an
+//archive has from the size of its banks before any of them are read
+const ENTRY_BYTES: u64 = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportOptions {
+    //threads that parse banks while the importing thread writes them to the database.
+    //0 or 1 parses on the importing thread, between inserts
+    pub workers: usize,
+    //chunks of CHUNK_SIZE entries each worker can have parsed ahead of the writer,
+    //this is what bounds how much of the dictionary is in memory at once
+    pub queue: usize,
+    //marks the dictionary as prefix_wildcards_supported, so search_wildcard matches
+    //a leading wildcard against its reversed columns instead of rejecting or scanning it
+    pub prefix_wildcards: bool,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        ImportOptions {
+            workers: std::thread::available_parallelism().map_or(1, |threads| threads.get()),
+            queue: 4,
+            prefix_wildcards: true,
+        }
+    }
+}
+
+impl ImportOptions {
+    //parses and inserts on the importing thread only
+    pub fn sequential() -> ImportOptions {
+        ImportOptions {
+            workers: 1,
+            ..ImportOptions::default()
+        }
+    }
+}
+
+//a run of consecutive entries from one bank
+enum ParsedChunk {
+    Tags(Vec<TagInformation>),
+    Terms(Vec<TermInformation>),
+    TermMeta(Vec<TermMeta>),
+    Kanji(Vec<KanjiInformation>),
+    KanjiMeta(Vec<KanjiMetaData>),
+}
+
+//what a worker sends the writer for each bank it parses: the bank's chunks in order,
+//then Done or Failed
+enum WorkerMessage {
+    Chunk(ParsedChunk),
+    Done,
+    Failed(ImportError),
+}
+
+//what a worker gets back from emit once the writer has stopped listening
+fn import_stopped() -> ImportError {
+    ImportError::Io(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "the import was stopped"))
+}
+
+fn emit_chunks<T, I, F>(bank: I, file: &str, wrap: fn(Vec<T>) -> ParsedChunk, emit: &mut F) -> Result<(), ImportError>
+where
+    I: Iterator<Item = Result<T, BankError>>,
+    F: FnMut(ParsedChunk) -> Result<(), ImportError>,
+{
+    let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+    for entry in bank {
+        let entry = entry.map_err(|source| ImportError::Bank {
+            file: file.to_string(),
+            source,
+        })?;
+        chunk.push(entry);
+
+        if chunk.len() == CHUNK_SIZE {
+            emit(wrap(std::mem::replace(&mut chunk, Vec::with_capacity(CHUNK_SIZE))))?;
+        }
+    }
+
+    if !chunk.is_empty() {
+        emit(wrap(chunk))?;
+    }
+    Ok(())
+}
+
+//reads a bank and hands its entries to emit, CHUNK_SIZE at a time and in the order they're in
+fn parse_bank<R, F>(archive: &mut ZipArchive<R>, bank: &BankFile, format: &Format, mut emit: F) -> Result<(), ImportError>
+where
+    R: Read + std::io::Seek,
+    F: FnMut(ParsedChunk) -> Result<(), ImportError>,
+{
+    let entries = archive.by_name(&bank.name)?;
+    let file = bank.name.as_str();
+
+    match bank.kind {
+        BankKind::Tag => emit_chunks(TagBankReader::new(entries), file, ParsedChunk::Tags, &mut emit),
+        BankKind::Term => emit_chunks(read_term_bank(entries, format), file, ParsedChunk::Terms, &mut emit),
+        BankKind::TermMeta => emit_chunks(TermMetaBankReader::new(entries), file, ParsedChunk::TermMeta, &mut emit),
+        BankKind::Kanji => emit_chunks(read_kanji_bank(entries, format), file, ParsedChunk::Kanji, &mut emit),
+        BankKind::KanjiMeta => emit_chunks(KanjiMetaBankReader::new(entries), file, ParsedChunk::KanjiMeta, &mut emit),
+    }
+}
+
+//worker n of a pool parses banks n, n + workers, n + 2 * workers and so on. each worker
+//has a channel of its own and the writer reads them in bank order, so while it waits on
+//one bank the workers parsing the next ones fill up their queues and then block
+fn parse_worker(
+    path: &Path,
+    banks: &[BankFile],
+    format: &Format,
+    worker: usize,
+    workers: usize,
+    sender: SyncSender<WorkerMessage>,
+) {
+    //zip archives can't be read from more than one thread, so each worker opens its own
+    let archive = File::open(path)
+        .map_err(ImportError::from)
+        .and_then(|file| ZipArchive::new(file).map_err(ImportError::from));
+    let mut archive = match archive {
+        Ok(archive) => archive,
+        Err(err) => {
+            let _ = sender.send(WorkerMessage::Failed(err));
+            return;
+        }
+    };
+
+    for bank in banks.iter().skip(worker).step_by(workers) {
+        let parsed = parse_bank(&mut archive, bank, format, |chunk| {
+            sender.send(WorkerMessage::Chunk(chunk)).map_err(|_| import_stopped())
+        });
+
+        let message = match parsed {
+            Ok(()) => WorkerMessage::Done,
+            Err(err) => WorkerMessage::Failed(err),
+        };
+        let failed = matches!(message, WorkerMessage::Failed(_));
+        if sender.send(message).is_err() || failed {
+            return;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportPhase {
+    //reading index.json and finding the banks in the archive
+    Validating,
+    Inserting,
+    //rebuilding the lookup indexes once every bank has been inserted,
+    //skipped when they were kept during the import
+    Indexing,
+}
+
+//snapshot of how far along an import is, sent to the progress callback
+//after each step so a ui can draw a progress bar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportProgress {
+    pub phase: ImportPhase,
+    pub current_file: Option<String>,
+    pub banks_done: usize,
+    pub banks_total: usize,
+    pub rows_inserted: usize,
+    //uncompressed bytes of the banks that have been read so far
+    pub bytes_read: u64,
+    pub bytes_total: u64,
+}
+
+impl ImportProgress {
+    fn new() -> ImportProgress {
+        ImportProgress {
+            phase: ImportPhase::Validating,
+            current_file: None,
+            banks_done: 0,
+            banks_total: 0,
+            rows_inserted: 0,
+            bytes_read: 0,
+            bytes_total: 0,
+        }
+    }
+}
+
+impl YomitanDatabase {
+    //imports every bank in a yomitan dictionary archive
+    pub fn import_archive<P: AsRef<Path>>(&self, path: P) -> Result<ImportSummary, ImportError> {
+        self.import_archive_with_options(path, ImportOptions::default(), |_| {})
+    }
+
+    //same as import_archive, but calls progress as the import moves along.
+    //the callback runs on the importing thread, to get updates somewhere else
+    //send them over a channel:
+    //  let (tx, rx) = std::sync::mpsc::channel();
+    //  db.import_archive_with_progress(path, |progress| { let _ = tx.send(progress.clone()); })
+    pub fn import_archive_with_progress<P, F>(&self, path: P, progress: F) -> Result<ImportSummary, ImportError>
+    where
+        P: AsRef<Path>,
+        F: FnMut(&ImportProgress),
+    {
+        self.import_archive_with_options(path, ImportOptions::default(), progress)
+    }
+
+    //banks are parsed by options.workers threads and written to the database by this one.
+    //rows are inserted in the same order no matter how many workers there are,
+    //so the database ends up the same as with ImportOptions::sequential()
+    pub fn import_archive_with_options<P, F>(
+        &self,
+        path: P,
+        options: ImportOptions,
+        mut progress: F,
+    ) -> Result<ImportSummary, ImportError>
+    where
+        P: AsRef<Path>,
+        F: FnMut(&ImportProgress),
+    {
+        let mut status = ImportProgress::new();
+
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let mut archive = ZipArchive::new(file)?;
+
+        status.current_file = Some(String::from("index.json"));
+        progress(&status);
+
+        let index: DictionaryIndex = match archive.index_for_name("index.json") {
+            Some(_) => read_json(&mut archive, "index.json")?,
+            None => return Err(ImportError::MissingIndex),
+        };
+
+        if self.dictionary_exists(&index.title)? {
+            return Err(ImportError::AlreadyImported(index.title));
+        }
+
+        //term and kanji banks are laid out differently in format 1,
+        //an index without a format is read as the current one
+        let format = index.format.clone().or(index.version.clone()).unwrap_or(Format::V3);
+
+        let banks = list_banks(&archive);
+        status.banks_total = banks.len();
+        let mut sizes = Vec::with_capacity(banks.len());
+        for bank in &banks {
+            sizes.push(archive.by_name(&bank.name)?.size());
+        }
+        status.bytes_total = sizes.iter().sum();
+        let workers = options.workers.min(banks.len());
+
+        //the whole dictionary is imported in one savepoint, if any bank fails then
+        //nothing from this archive (including the index) is left in the database
+        self.with_savepoint("import_archive", || {
+            self.insert_index(&index, options.prefix_wildcards)?;
+
+            //indexes get rebuilt once everything is inserted, but only when it's cheaper
+            //than keeping them up to date. the other dictionaries stay indexed otherwise
+            let rebuild_indexes = self.should_rebuild_indexes(status.bytes_total)?;
+            if rebuild_indexes {
+                migrations::drop_indexes(&self.connection)?;
+            }
+
+            let dictionary = index.title.as_str();
+            let mut media_paths = BTreeSet::new();
+            let mut summary = ImportSummary {
+                title: index.title.clone(),
+                //insert_index already added the tags from the index
+                tags: index.tag_meta.as_ref().map_or(0, |tags| tags.len()),
+                ..Default::default()
+            };
+
+            status.phase = ImportPhase::Inserting;
+            if workers <= 1 {
+                for (bank, size) in banks.iter().zip(&sizes) {
+                    status.current_file = Some(bank.name.clone());
+                    progress(&status);
+
+                    let mut rows = 0;
+                    parse_bank(&mut archive, bank, &format, |chunk| {
+                        rows += self.insert_chunk(chunk, dictionary, &mut summary, &mut media_paths)?;
+                        Ok(())
+                    })?;
+
+                    status.banks_done += 1;
+                    status.rows_inserted += rows;
+                    status.bytes_read += size;
+                    progress(&status);
+                }
+            } else {
+                std::thread::scope(|scope| -> Result<(), ImportError> {
+                    let mut receivers = Vec::with_capacity(workers);
+                    for worker in 0..workers {
+                        let (sender, receiver) = sync_channel(options.queue);
+                        receivers.push(receiver);
+
+                        let (banks, format) = (&banks, &format);
+                        scope.spawn(move || parse_worker(path, banks, format, worker, workers, sender));
+                    }
+
+                    //returning early drops the receivers, which stops the workers
+                    for (index, (bank, size)) in banks.iter().zip(&sizes).enumerate() {
+                        status.current_file = Some(bank.name.clone());
+                        progress(&status);
+
+                        let mut rows = 0;
+                        loop {
+                            match receivers[index % workers].recv() {
+                                Ok(WorkerMessage::Chunk(chunk)) => {
+                                    rows += self.insert_chunk(chunk, dictionary, &mut summary, &mut media_paths)?;
+                                }
+                                Ok(WorkerMessage::Done) => break,
+                                Ok(WorkerMessage::Failed(err)) => return Err(err),
+                                //the worker is gone without saying why, which means it panicked
+                                Err(_) => return Err(import_stopped()),
+                            }
+                        }
+
+                        status.banks_done += 1;
+                        status.rows_inserted += rows;
+                        status.bytes_read += size;
+                        progress(&status);
+                    }
+                    Ok(())
+                })?;
+            }
+
+            //only files that a definition points at are stored, paths that
+            //aren't in the archive are left for the renderer to skip
+            for path in &media_paths {
+                let mut file = match archive.by_name(path) {
+                    Ok(file) => file,
+                    Err(zip::result::ZipError::FileNotFound) => continue,
+                    Err(err) => return Err(err.into()),
+                };
+
+                status.current_file = Some(path.clone());
+                progress(&status);
+
+                let mut content = Vec::with_capacity(file.size() as usize);
+                file.read_to_end(&mut content)?;
+                self.insert_media(dictionary, path, &content)?;
+                summary.media += 1;
+            }
+
+            //a stylesheet for the dictionary's structured content, kept as it is
+            if archive.index_for_name("styles.css").is_some() {
+                let mut styles = String::new();
+                archive.by_name("styles.css")?.read_to_string(&mut styles)?;
+                self.connection
+                    .execute("UPDATE dictionaries SET styles = ?1 WHERE title = ?2", (styles, dictionary))?;
+            }
+
+            if rebuild_indexes {
+                status.phase = ImportPhase::Indexing;
+                status.current_file = None;
+                progress(&status);
+
+                migrations::create_indexes(&self.connection)?;
+            }
+
+            self.connection.execute(
+                "UPDATE dictionaries SET counts = ?1 WHERE title = ?2",
+                (summary.terms, dictionary),
+            )?;
+
+            Ok(summary)
+        })
+    }
+}
+
+impl YomitanDatabase {
+    //dropping the indexes and building them again covers every row in the database,
+    //so it's only worth it when the tables are empty or the archive brings a lot
+    //more rows than are already there
+    fn should_rebuild_indexes(&self, bank_bytes: u64) -> Result<bool, rusqlite::Error> {
+        let existing: u64 = self.connection.query_row(
+            "SELECT (SELECT COUNT(*) FROM terms) + (SELECT COUNT(*) FROM termMeta) \
+                + (SELECT COUNT(*) FROM kanji) + (SELECT COUNT(*) FROM kanjiMeta)",
+            [],
+            |row| row.get(0),
+        )?;
+        let incoming = bank_bytes / ENTRY_BYTES;
+        Ok(existing == 0 || incoming > existing.saturating_mul(2))
+    }
+
+    //writes a chunk of a bank, returns the number of rows inserted
+    fn insert_chunk(
+        &self,
+        chunk: ParsedChunk,
+        dictionary: &str,
+        summary: &mut ImportSummary,
+        media_paths: &mut BTreeSet<String>,
+    ) -> Result<usize, rusqlite::Error> {
+        let rows = match chunk {
+            ParsedChunk::Tags(tags) => {
+                let rows = self.insert_tag_bank(tags, dictionary)?;
+                summary.tags += rows;
+                rows
+            }
+            ParsedChunk::Terms(terms) => {
+                for term in &terms {
+                    collect_image_paths(&term.definitions, media_paths);
+                }
+                let rows = self.insert_term_bank(terms, dictionary)?;
+                summary.terms += rows;
+                rows
+            }
+            ParsedChunk::TermMeta(metas) => {
+                let rows = self.insert_term_meta_bank(metas, dictionary)?;
+                summary.term_meta += rows;
+                rows
+            }
+            ParsedChunk::Kanji(kanji) => {
+                let rows = self.insert_kanji_bank(kanji, dictionary)?;
+                summary.kanji += rows;
+                rows
+            }
+            ParsedChunk::KanjiMeta(metas) => {
+                let rows = self.insert_kanji_meta_bank(metas, dictionary)?;
+                summary.kanji_meta += rows;
+                rows
+            }
+        };
+        Ok(rows)
+    }
+}
@@ -0,0 +1,235 @@
+use crate::YomitanDatabase;
+use rusqlite::types::Type;
+use rusqlite::{Error, Row};
+use schemas::*;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchType {
+    Exact,
+    Prefix,
+    //uses the reversed columns, so this is a prefix search on the reversed query
+    Suffix,
+}
+
+//a term as it was stored in the database, along with which dictionary it came from
+#[derive(Debug)]
+pub struct DatabaseTerm {
+    pub id: i64,
+    pub dictionary: String,
+    pub term: TermInformation,
+}
+
+pub(crate) const TERM_COLUMNS: &str =
+    "terms.id, terms.definitionTags, terms.dictionary, terms.expression, terms.glossary, terms.reading, \
+     terms.rules, terms.score, terms.sequence, terms.termTags";
+
+//lookups only return rows from enabled dictionaries, ordered by the dictionaries' priority
+pub(crate) const JOIN_DICTIONARIES: &str = "JOIN dictionaries ON dictionaries.title = terms.dictionary";
+
+//builds a DatabaseTerm from a row selected with TERM_COLUMNS
+pub(crate) fn term_from_row(row: &Row) -> Result<DatabaseTerm, Error> {
+    let definitions: Vec<TermDefinition> = json_column(row, "glossary")?;
+
+    Ok(DatabaseTerm {
+        id: row.get("id")?,
+        dictionary: row.get("dictionary")?,
+        term: TermInformation {
+            term: row.get("expression")?,
+            reading: row.get("reading")?,
+            definition_tags: row.get("definitionTags")?,
+            deinflectors: row.get("rules")?,
+            popularity: row.get("score")?,
+            definitions,
+            sequence_number: row.get("sequence")?,
+            term_tags: row.get("termTags")?,
+        },
+    })
+}
+
+#[derive(Debug)]
+pub struct DatabaseKanji {
+    pub id: i64,
+    pub dictionary: String,
+    pub kanji: KanjiInformation,
+}
+
+#[derive(Debug)]
+pub struct DatabaseKanjiMeta {
+    pub id: i64,
+    pub dictionary: String,
+    pub meta: KanjiMetaData,
+}
+
+//parses a column that was stored as json
+pub(crate) fn json_column<T: serde::de::DeserializeOwned>(row: &Row, column: &str) -> Result<T, Error> {
+    let text: String = row.get(column)?;
+    serde_json::from_str(&text).map_err(|err| {
+        let index = row.as_ref().column_index(column).unwrap_or_default();
+        Error::FromSqlConversionFailure(index, Type::Text, Box::new(err))
+    })
+}
+
+fn kanji_from_row(row: &Row) -> Result<DatabaseKanji, Error> {
+    let onyomi: String = row.get("onyomi")?;
+    let kunyomi: String = row.get("kunyomi")?;
+
+    Ok(DatabaseKanji {
+        id: row.get("id")?,
+        dictionary: row.get("dictionary")?,
+        kanji: KanjiInformation {
+            kanji: row.get("character")?,
+            onyomi: onyomi.split_whitespace().map(String::from).collect(),
+            kunyomi: kunyomi.split_whitespace().map(String::from).collect(),
+            tags: row.get("tag")?,
+            meanings: json_column(row, "meanings")?,
+            stats: json_column(row, "stats")?,
+        },
+    })
+}
+
+fn kanji_meta_from_row(row: &Row) -> Result<DatabaseKanjiMeta, Error> {
+    Ok(DatabaseKanjiMeta {
+        id: row.get("id")?,
+        dictionary: row.get("dictionary")?,
+        meta: KanjiMetaData {
+            character: row.get("character")?,
+            mode: row.get("mode")?,
+            data: json_column(row, "data")?,
+        },
+    })
+}
+
+//term meta for an expression from a single dictionary
+#[derive(Debug)]
+pub struct DictionaryTermMeta {
+    pub dictionary: String,
+    pub meta: Vec<TermMeta>,
+}
+
+//the data column only holds the third element of the entry,
+//so the entry gets put back together and parsed the same way the bank is
+pub(crate) fn term_meta_from_row(row: &Row) -> Result<(String, TermMeta), Error> {
+    let expression: String = row.get("expression")?;
+    let mode: String = row.get("mode")?;
+    let data: serde_json::Value = json_column(row, "data")?;
+
+    let entry = serde_json::Value::Array(vec![expression.into(), mode.into(), data]);
+    let meta = TermMeta::deserialize(entry).map_err(|err| {
+        let index = row.as_ref().column_index("data").unwrap_or_default();
+        Error::FromSqlConversionFailure(index, Type::Text, Box::new(err))
+    })?;
+
+    Ok((row.get("dictionary")?, meta))
+}
+
+//escapes the characters GLOB treats as wildcards so the query is matched literally
+pub(crate) fn escape_glob(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '*' | '?' | '[' => {
+                escaped.push('[');
+                escaped.push(c);
+                escaped.push(']');
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl YomitanDatabase {
+    //finds terms where either the expression or the reading matches the query
+    pub fn lookup_terms(&self, query: &str, match_type: MatchType) -> Result<Vec<DatabaseTerm>, Error> {
+        //GLOB is used instead of LIKE since it is case sensitive and can use an index
+        let (condition, parameter) = match match_type {
+            MatchType::Exact => (
+                "terms.expression = ?1 OR terms.reading = ?1",
+                query.to_string(),
+            ),
+            MatchType::Prefix => (
+                "terms.expression GLOB ?1 OR terms.reading GLOB ?1",
+                escape_glob(query) + "*",
+            ),
+            MatchType::Suffix => (
+                "terms.expressionReverse GLOB ?1 OR terms.readingReverse GLOB ?1",
+                escape_glob(&query.chars().rev().collect::<String>()) + "*",
+            ),
+        };
+
+        let sql = format!(
+            "SELECT {} FROM terms {} WHERE dictionaries.enabled AND ({}) ORDER BY dictionaries.priority, terms.id",
+            TERM_COLUMNS, JOIN_DICTIONARIES, condition
+        );
+
+        let mut statement = self.connection.prepare_cached(&sql)?;
+        let rows = statement.query_map([parameter], term_from_row)?;
+        rows.collect()
+    }
+
+    //exact match on the expression, and on the reading too if one is given
+    pub fn lookup_term(&self, expression: &str, reading: Option<&str>) -> Result<Vec<DatabaseTerm>, Error> {
+        let sql = format!(
+            "SELECT {} FROM terms {} \
+             WHERE dictionaries.enabled AND terms.expression = ?1 AND (?2 IS NULL OR terms.reading = ?2) \
+             ORDER BY dictionaries.priority, terms.id",
+            TERM_COLUMNS, JOIN_DICTIONARIES
+        );
+
+        let mut statement = self.connection.prepare_cached(&sql)?;
+        let rows = statement.query_map((expression, reading), term_from_row)?;
+        rows.collect()
+    }
+
+    //every dictionary's entry for a kanji
+    pub fn lookup_kanji(&self, kanji: char) -> Result<Vec<DatabaseKanji>, Error> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT kanji.id, kanji.character, kanji.dictionary, kanji.kunyomi, kanji.meanings, kanji.onyomi, \
+             kanji.stats, kanji.tag FROM kanji JOIN dictionaries ON dictionaries.title = kanji.dictionary \
+             WHERE dictionaries.enabled AND kanji.character = ?1 ORDER BY dictionaries.priority, kanji.id",
+        )?;
+        let rows = statement.query_map([kanji.to_string()], kanji_from_row)?;
+        rows.collect()
+    }
+
+    //frequency data for a kanji from the kanji meta banks
+    pub fn lookup_kanji_meta(&self, kanji: char) -> Result<Vec<DatabaseKanjiMeta>, Error> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT kanjiMeta.id, kanjiMeta.character, kanjiMeta.dictionary, kanjiMeta.data, kanjiMeta.mode \
+             FROM kanjiMeta JOIN dictionaries ON dictionaries.title = kanjiMeta.dictionary \
+             WHERE dictionaries.enabled AND kanjiMeta.character = ?1 ORDER BY dictionaries.priority, kanjiMeta.id",
+        )?;
+        let rows = statement.query_map([kanji.to_string()], kanji_meta_from_row)?;
+        rows.collect()
+    }
+
+    //frequency, pitch and ipa data for a term, grouped by dictionary.
+    //entries for a different reading are left out, frequencies without a reading are kept
+    pub fn lookup_term_meta(&self, expression: &str, reading: &str) -> Result<Vec<DictionaryTermMeta>, Error> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT termMeta.id, termMeta.dictionary, termMeta.data, termMeta.expression, termMeta.mode \
+             FROM termMeta JOIN dictionaries ON dictionaries.title = termMeta.dictionary \
+             WHERE dictionaries.enabled AND termMeta.expression = ?1 ORDER BY dictionaries.priority, termMeta.id",
+        )?;
+        let rows = statement.query_map([expression], term_meta_from_row)?;
+
+        let mut grouped: Vec<DictionaryTermMeta> = Vec::new();
+        for row in rows {
+            let (dictionary, meta) = row?;
+            if meta.reading().is_some_and(|meta_reading| meta_reading != reading) {
+                continue;
+            }
+
+            match grouped.iter_mut().find(|group| group.dictionary == dictionary) {
+                Some(group) => group.meta.push(meta),
+                None => grouped.push(DictionaryTermMeta {
+                    dictionary,
+                    meta: vec![meta],
+                }),
+            }
+        }
+
+        Ok(grouped)
+    }
+}
@@ -1,31 +1,39 @@
-use yomi_dict_db::YomitanDatabase;
-use std::error::Error;
-use std::fs::File;
-use std::io::BufReader;
-use schemas::DictionaryTermBankV3;
+use yomi_dict_db::{validate_archive, YomitanDatabase};
 
 fn main() -> std::io::Result<()> {
-    let db = YomitanDatabase::open_database("test.db").unwrap();
+    //--validate checks the archive and prints every problem in it instead of importing
+    let validate = std::env::args().nth(1).as_deref() == Some("--validate");
+    let path = std::env::args()
+        .nth(if validate { 2 } else { 1 })
+        .unwrap_or(String::from("C:\\code\\yomidb\\testzip\\jitendex.zip"));
 
-    let path = "C:\\code\\yomidb\\testzip\\term_bank_1.json";
-    //let path = "C:\\code\\yomidb\\testzip\\term_bank_1.json";
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    let jd = &mut serde_json::Deserializer::from_reader(reader);
-    let result: Result<DictionaryTermBankV3, _> = serde_path_to_error::deserialize(jd);
+    if validate {
+        match validate_archive(&path) {
+            Ok(report) => {
+                for diagnostic in &report.diagnostics {
+                    println!("{}", diagnostic);
+                }
+                println!(
+                    "{} errors, {} warnings",
+                    report.errors().count(),
+                    report.warnings().count()
+                );
+            }
+            Err(err) => {
+                println!("Fail {}", err);
+            }
+        }
+        return Ok(());
+    }
 
-    match result {
-        Ok(_) => {
-            let term_bank = result.unwrap();
+    let db = YomitanDatabase::open_database("test.db").unwrap();
 
-            for term in term_bank {
-                db.insert_term(term, "TestDict").unwrap();
-            }
-            
+    match db.import_archive(&path) {
+        Ok(summary) => {
+            println!("Imported {:#?}", summary);
         }
         Err(err) => {
-            let path = err.path().to_string();
-            println!("Fail {:#?}", path);
+            println!("Fail {}", err);
         }
     }
     Ok(())
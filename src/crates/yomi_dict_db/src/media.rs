@@ -0,0 +1,281 @@
+use crate::YomitanDatabase;
+use rusqlite::{params, Error, OptionalExtension};
+use schemas::*;
+use std::collections::BTreeSet;
+
+//a media file that was imported with a dictionary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Media {
+    pub dictionary: String,
+    pub path: String,
+    pub mediatype: String,
+    pub width: u32,
+    pub height: u32,
+    pub content: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MediaInfo {
+    pub mediatype: &'static str,
+    pub width: u32,
+    pub height: u32,
+}
+
+//figures out the type and dimensions of an image from its header.
+//returns None for anything that isn't png, jpeg, gif, webp or svg
+pub fn sniff_media(content: &[u8]) -> Option<MediaInfo> {
+    if content.starts_with(b"\x89PNG\r\n\x1a\n") {
+        sniff_png(content)
+    } else if content.starts_with(b"\xff\xd8") {
+        sniff_jpeg(content)
+    } else if content.starts_with(b"GIF87a") || content.starts_with(b"GIF89a") {
+        sniff_gif(content)
+    } else if content.len() >= 12 && &content[0..4] == b"RIFF" && &content[8..12] == b"WEBP" {
+        sniff_webp(content)
+    } else {
+        sniff_svg(content)
+    }
+}
+
+fn u16_be(content: &[u8], offset: usize) -> Option<u32> {
+    let bytes = content.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]) as u32)
+}
+
+fn u16_le(content: &[u8], offset: usize) -> Option<u32> {
+    let bytes = content.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]) as u32)
+}
+
+fn u24_le(content: &[u8], offset: usize) -> Option<u32> {
+    let bytes = content.get(offset..offset + 3)?;
+    Some(bytes[0] as u32 | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16)
+}
+
+fn u32_be(content: &[u8], offset: usize) -> Option<u32> {
+    let bytes = content.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+//width and height are the first two fields of the IHDR chunk
+fn sniff_png(content: &[u8]) -> Option<MediaInfo> {
+    if content.get(12..16)? != b"IHDR" {
+        return None;
+    }
+
+    Some(MediaInfo {
+        mediatype: "image/png",
+        width: u32_be(content, 16)?,
+        height: u32_be(content, 20)?,
+    })
+}
+
+fn sniff_gif(content: &[u8]) -> Option<MediaInfo> {
+    Some(MediaInfo {
+        mediatype: "image/gif",
+        width: u16_le(content, 6)?,
+        height: u16_le(content, 8)?,
+    })
+}
+
+//walks the segments until a start of frame, which holds the dimensions
+fn sniff_jpeg(content: &[u8]) -> Option<MediaInfo> {
+    let mut offset = 2;
+    loop {
+        if *content.get(offset)? != 0xff {
+            return None;
+        }
+        let marker = *content.get(offset + 1)?;
+
+        //fill bytes and markers without a length
+        if marker == 0xff {
+            offset += 1;
+            continue;
+        }
+        if marker == 0x01 || (0xd0..=0xd8).contains(&marker) {
+            offset += 2;
+            continue;
+        }
+
+        //SOF0 through SOF15, except DHT, JPG and DAC which share the range
+        if (0xc0..=0xcf).contains(&marker) && marker != 0xc4 && marker != 0xc8 && marker != 0xcc {
+            return Some(MediaInfo {
+                mediatype: "image/jpeg",
+                width: u16_be(content, offset + 7)?,
+                height: u16_be(content, offset + 5)?,
+            });
+        }
+
+        offset += 2 + u16_be(content, offset + 2)? as usize;
+    }
+}
+
+fn sniff_webp(content: &[u8]) -> Option<MediaInfo> {
+    let (width, height) = match content.get(12..16)? {
+        //lossy, dimensions are in the frame header after the start code
+        b"VP8 " => (u16_le(content, 26)? & 0x3fff, u16_le(content, 28)? & 0x3fff),
+        //lossless, 14 bits each packed after the signature byte
+        b"VP8L" => {
+            let bits = content.get(21..25)?;
+            let packed = u32::from_le_bytes([bits[0], bits[1], bits[2], bits[3]]);
+            ((packed & 0x3fff) + 1, ((packed >> 14) & 0x3fff) + 1)
+        }
+        //extended, canvas size is stored minus one
+        b"VP8X" => (u24_le(content, 24)? + 1, u24_le(content, 27)? + 1),
+        _ => return None,
+    };
+
+    Some(MediaInfo {
+        mediatype: "image/webp",
+        width,
+        height,
+    })
+}
+
+//reads the width and height attributes of the root svg element,
+//falling back to the viewBox when they are missing
+fn sniff_svg(content: &[u8]) -> Option<MediaInfo> {
+    let text = std::str::from_utf8(content).ok()?;
+    let start = text.find("<svg")?;
+    let end = start + text[start..].find('>')?;
+    let tag = &text[start..end];
+
+    let view_box: Vec<f64> = svg_attribute(tag, "viewBox")
+        .map(|value| {
+            value
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter_map(|number| number.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let width = svg_attribute(tag, "width")
+        .and_then(svg_length)
+        .or_else(|| view_box.get(2).copied())
+        .unwrap_or_default();
+    let height = svg_attribute(tag, "height")
+        .and_then(svg_length)
+        .or_else(|| view_box.get(3).copied())
+        .unwrap_or_default();
+
+    Some(MediaInfo {
+        mediatype: "image/svg+xml",
+        width: width.round() as u32,
+        height: height.round() as u32,
+    })
+}
+
+fn svg_attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = tag;
+    while let Some(position) = rest.find(name) {
+        let preceded_by_space = rest[..position].ends_with(|c: char| c.is_whitespace());
+        let after = rest[position + name.len()..].trim_start();
+        rest = &rest[position + name.len()..];
+
+        if !preceded_by_space {
+            continue;
+        }
+        if let Some(value) = after.strip_prefix('=') {
+            let value = value.trim_start();
+            let quote = value.chars().next()?;
+            if quote != '"' && quote != '\'' {
+                return None;
+            }
+            let value = &value[1..];
+            return Some(&value[..value.find(quote)?]);
+        }
+    }
+    None
+}
+
+//"120", "120px" and "12.5em" all give their number, percentages can't be resolved
+fn svg_length(value: &str) -> Option<f64> {
+    let value = value.trim();
+    if value.ends_with('%') {
+        return None;
+    }
+    let number = value.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    number.parse().ok()
+}
+
+//collects the paths of every image a term's definitions point at
+pub fn collect_image_paths(definitions: &[TermDefinition], paths: &mut BTreeSet<String>) {
+    fn walk(node: &StructuredContentNode, paths: &mut BTreeSet<String>) {
+        match node {
+            StructuredContentNode::Text(_) => (),
+            StructuredContentNode::ChildContent(children) => {
+                for child in children {
+                    walk(child, paths);
+                }
+            }
+            StructuredContentNode::Variant(element) => {
+                let content = match element.as_ref() {
+                    TagElement::Image(image) => {
+                        paths.insert(image.path.clone());
+                        None
+                    }
+                    TagElement::LineBreak(_) => None,
+                    TagElement::Unstyled(elem) => elem.content.as_ref(),
+                    TagElement::Table(elem) => elem.content.as_ref(),
+                    TagElement::Styled(elem) => elem.content.as_ref(),
+                    TagElement::Link(elem) => elem.content.as_ref(),
+                };
+                if let Some(content) = content {
+                    walk(content, paths);
+                }
+            }
+        }
+    }
+
+    for definition in definitions {
+        match definition {
+            TermDefinition::Detailed(DetailedDefinition::Image(image)) => {
+                paths.insert(image.path.clone());
+            }
+            TermDefinition::Detailed(DetailedDefinition::StructuredContent(node)) => walk(node, paths),
+            _ => (),
+        }
+    }
+}
+
+impl YomitanDatabase {
+    //stores a media file, the type and dimensions are sniffed from the content.
+    //files that can't be recognized are kept as application/octet-stream with no size
+    pub fn insert_media(&self, dictionary: &str, path: &str, content: &[u8]) -> Result<(), Error> {
+        const INSERT_QUERY: &str = "INSERT INTO media (id, dictionary, height, width, mediatype, path, content) \
+                                    VALUES ((SELECT COUNT(*) FROM media WHERE dictionary = ?1), ?1, ?2, ?3, ?4, ?5, ?6)";
+
+        let info = sniff_media(content).unwrap_or(MediaInfo {
+            mediatype: "application/octet-stream",
+            width: 0,
+            height: 0,
+        });
+
+        self.connection.prepare_cached(INSERT_QUERY)?.execute(params![dictionary,
+                                                                      info.height,
+                                                                      info.width,
+                                                                      info.mediatype,
+                                                                      path,
+                                                                      content])?;
+        Ok(())
+    }
+
+    pub fn get_media(&self, dictionary: &str, path: &str) -> Result<Option<Media>, Error> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT dictionary, path, mediatype, width, height, content FROM media WHERE dictionary = ?1 AND path = ?2",
+        )?;
+
+        statement
+            .query_row((dictionary, path), |row| {
+                Ok(Media {
+                    dictionary: row.get("dictionary")?,
+                    path: row.get("path")?,
+                    mediatype: row.get("mediatype")?,
+                    width: row.get("width")?,
+                    height: row.get("height")?,
+                    content: row.get("content")?,
+                })
+            })
+            .optional()
+    }
+}
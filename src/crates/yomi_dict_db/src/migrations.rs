@@ -0,0 +1,302 @@
+use crate::search;
+use rusqlite::{Connection, Error};
+use std::fmt;
+
+//a single step in the database layout, migrations run in order and each one
+//bumps PRAGMA user_version by one. never edit a migration that has shipped,
+//add a new one to the end of MIGRATIONS instead
+pub(crate) struct Migration {
+    pub description: &'static str,
+    pub apply: fn(&Connection) -> Result<(), Error>,
+}
+
+pub(crate) const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "initial tables",
+        apply: initial_tables,
+    },
+    Migration {
+        description: "kanji characters, kanji meta, dictionary enabled and priority",
+        apply: kanji_meta_and_dictionary_order,
+    },
+    Migration {
+        description: "lookup indexes",
+        apply: lookup_indexes,
+    },
+    Migration {
+        description: "glossary full text search",
+        apply: glossary_full_text_search,
+    },
+    Migration {
+        description: "dictionary stylesheets",
+        apply: dictionary_styles,
+    },
+    Migration {
+        description: "dictionary languages, frequency mode and minimum yomitan version",
+        apply: dictionary_index_metadata,
+    },
+    Migration {
+        description: "glossary full text search from plain text definitions",
+        apply: rebuild_glossary_full_text_search,
+    },
+];
+
+//secondary indexes used by lookups, as (name, table(columns)). these are what imports
+//drop and rebuild, a new index goes here and in a new migration that creates it
+pub(crate) const INDEXES: &[(&str, &str)] = &[
+    ("dictionaries_title", "dictionaries(title)"),
+    ("terms_expression", "terms(expression)"),
+    ("terms_reading", "terms(reading)"),
+    ("terms_expressionReverse", "terms(expressionReverse)"),
+    ("terms_readingReverse", "terms(readingReverse)"),
+    ("terms_sequence", "terms(sequence)"),
+    ("terms_dictionary", "terms(dictionary)"),
+    ("termMeta_expression", "termMeta(expression)"),
+    ("termMeta_dictionary", "termMeta(dictionary)"),
+    ("kanji_character", "kanji(character)"),
+    ("kanji_dictionary", "kanji(dictionary)"),
+    ("kanjiMeta_character", "kanjiMeta(character)"),
+    ("kanjiMeta_dictionary", "kanjiMeta(dictionary)"),
+    ("tagMeta_dictionary_name", "tagMeta(dictionary, name)"),
+    ("media_dictionary_path", "media(dictionary, path)"),
+];
+
+//the newest layout this version of the library knows how to read
+pub const SCHEMA_VERSION: i64 = MIGRATIONS.len() as i64;
+
+#[derive(Debug)]
+pub enum OpenError {
+    Database(Error),
+    //the database was written by a newer version of the library
+    UnsupportedVersion { found: i64, supported: i64 },
+}
+
+impl fmt::Display for OpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenError::Database(err) => write!(f, "database error: {}", err),
+            OpenError::UnsupportedVersion { found, supported } => write!(
+                f,
+                "database version {} is newer than the newest supported version {}",
+                found, supported
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OpenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OpenError::Database(err) => Some(err),
+            OpenError::UnsupportedVersion { .. } => None,
+        }
+    }
+}
+
+impl From<Error> for OpenError {
+    fn from(err: Error) -> Self {
+        OpenError::Database(err)
+    }
+}
+
+pub fn schema_version(conn: &Connection) -> Result<i64, Error> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+//brings the database up to SCHEMA_VERSION, each migration is applied in its own transaction
+pub(crate) fn migrate(conn: &Connection) -> Result<(), OpenError> {
+    let version = schema_version(conn)?;
+    if version > SCHEMA_VERSION {
+        return Err(OpenError::UnsupportedVersion {
+            found: version,
+            supported: SCHEMA_VERSION,
+        });
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let target = index as i64 + 1;
+
+        let tx = conn.unchecked_transaction()?;
+        (migration.apply)(&tx)?;
+        tx.pragma_update(None, "user_version", target)?;
+        tx.commit()?;
+
+        println!("Migrated database to version {} ({})", target, migration.description);
+    }
+
+    Ok(())
+}
+
+fn create_index_list(conn: &Connection, indexes: &[(&str, &str)]) -> Result<(), Error> {
+    for (name, columns) in indexes {
+        conn.execute_batch(&format!("CREATE INDEX IF NOT EXISTS {} ON {}", name, columns))?;
+    }
+    Ok(())
+}
+
+pub(crate) fn create_indexes(conn: &Connection) -> Result<(), Error> {
+    create_index_list(conn, INDEXES)
+}
+
+//inserting into a table without indexes and building them afterwards
+//is a lot faster than keeping them up to date row by row
+pub(crate) fn drop_indexes(conn: &Connection) -> Result<(), Error> {
+    for (name, _) in INDEXES {
+        conn.execute_batch(&format!("DROP INDEX IF EXISTS {}", name))?;
+    }
+    Ok(())
+}
+
+fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool, Error> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2)",
+        [table, column],
+        |row| row.get(0),
+    )
+}
+
+//adds a column unless an unversioned database already has it
+fn add_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<(), Error> {
+    if !column_exists(conn, table, column)? {
+        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))?;
+    }
+    Ok(())
+}
+
+//the layout from before databases were versioned. IF NOT EXISTS is kept
+//so that databases created back then are picked up as version 1
+fn initial_tables(conn: &Connection) -> Result<(), Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS dictionaries(
+             id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+             title TEXT NOT NULL,
+             version INTEGER,
+             revision TEXT NOT NULL,
+             import_date DATE,
+             prefix_wildcards_supported BOOLEAN,
+             sequenced BOOLEAN,
+             counts INTEGER DEFAULT 0);
+
+         CREATE TABLE IF NOT EXISTS kanji(
+             id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+             dictionary TEXT NOT NULL,
+             kunyomi TEXT NOT NULL,
+             meanings TEXT NOT NULL,
+             onyomi TEXT NOT NULL,
+             stats TEXT NOT NULL,
+             tag TEXT NOT NULL);
+
+         CREATE TABLE IF NOT EXISTS media(
+             key INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+             id INTEGER NOT NULL,
+             dictionary TEXT NOT NULL,
+             height INTEGER NOT NULL,
+             width INTEGER NOT NULL,
+             mediatype TEXT NOT NULL,
+             path TEXT NOT NULL,
+             content BLOB NOT NULL);
+
+         CREATE TABLE IF NOT EXISTS tagMeta(
+             id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+             dictionary TEXT NOT NULL,
+             category TEXT NOT NULL,
+             name TEXT NOT NULL,
+             notes TEXT NOT NULL,
+             sortOrder INTEGER NOT NULL,
+             score INTEGER NOT NULL);
+
+         CREATE TABLE IF NOT EXISTS termMeta(
+             id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+             dictionary TEXT NOT NULL,
+             data TEXT NOT NULL,
+             expression TEXT NOT NULL,
+             mode TEXT NOT NULL);
+
+         CREATE TABLE IF NOT EXISTS terms(
+             id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+             definitionTags TEXT NOT NULL,
+             dictionary TEXT NOT NULL,
+             expression TEXT NOT NULL,
+             expressionReverse TEXT NOT NULL,
+             glossary TEXT NOT NULL,
+             reading TEXT NOT NULL,
+             readingReverse TEXT NOT NULL,
+             rules TEXT NOT NULL,
+             score INTEGER NOT NULL,
+             sequence INTEGER NOT NULL,
+             termTags TEXT NOT NULL);",
+    )
+}
+
+fn kanji_meta_and_dictionary_order(conn: &Connection) -> Result<(), Error> {
+    add_column(conn, "kanji", "character", "TEXT NOT NULL DEFAULT ''")?;
+    add_column(conn, "dictionaries", "enabled", "BOOLEAN DEFAULT 1")?;
+    add_column(conn, "dictionaries", "priority", "INTEGER DEFAULT 0")?;
+
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS kanjiMeta(
+             id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+             character TEXT NOT NULL,
+             dictionary TEXT NOT NULL,
+             data TEXT NOT NULL,
+             mode TEXT NOT NULL);",
+    )
+}
+
+//the indexes as they were when this migration shipped, kept apart from
+//INDEXES so that changing those doesn't change what this migration does
+fn lookup_indexes(conn: &Connection) -> Result<(), Error> {
+    create_index_list(
+        conn,
+        &[
+            ("dictionaries_title", "dictionaries(title)"),
+            ("terms_expression", "terms(expression)"),
+            ("terms_reading", "terms(reading)"),
+            ("terms_expressionReverse", "terms(expressionReverse)"),
+            ("terms_readingReverse", "terms(readingReverse)"),
+            ("terms_sequence", "terms(sequence)"),
+            ("terms_dictionary", "terms(dictionary)"),
+            ("termMeta_expression", "termMeta(expression)"),
+            ("termMeta_dictionary", "termMeta(dictionary)"),
+            ("kanji_character", "kanji(character)"),
+            ("kanji_dictionary", "kanji(dictionary)"),
+            ("kanjiMeta_character", "kanjiMeta(character)"),
+            ("kanjiMeta_dictionary", "kanjiMeta(dictionary)"),
+            ("tagMeta_dictionary_name", "tagMeta(dictionary, name)"),
+            ("media_dictionary_path", "media(dictionary, path)"),
+        ],
+    )
+}
+
+//rowid is the id of the term the glossary belongs to. terms that are already
+//in the database get indexed by rebuild_glossary_full_text_search
+fn glossary_full_text_search(conn: &Connection) -> Result<(), Error> {
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS termsFts USING fts5(
+             glossary,
+             tokenize = 'unicode61 remove_diacritics 2');",
+    )
+}
+
+//styles is the styles.css that came with the dictionary,
+//user_styles are overrides added afterwards that get applied on top of it
+fn dictionary_styles(conn: &Connection) -> Result<(), Error> {
+    add_column(conn, "dictionaries", "styles", "TEXT")?;
+    add_column(conn, "dictionaries", "user_styles", "TEXT")
+}
+
+//fields from index.json that used to be dropped on import. dictionaries imported
+//before this have them all NULL, which is the same as an index that leaves them out
+fn dictionary_index_metadata(conn: &Connection) -> Result<(), Error> {
+    add_column(conn, "dictionaries", "source_language", "TEXT")?;
+    add_column(conn, "dictionaries", "target_language", "TEXT")?;
+    add_column(conn, "dictionaries", "frequency_mode", "TEXT")?;
+    add_column(conn, "dictionaries", "minimum_yomitan_version", "TEXT")
+}
+
+//indexes every term that's in the database. anything already in termsFts came from
+//an older extraction of the definitions' text, so it's thrown out first
+fn rebuild_glossary_full_text_search(conn: &Connection) -> Result<(), Error> {
+    conn.execute_batch("DELETE FROM termsFts")?;
+    search::index_existing_glossaries(conn)
+}
@@ -0,0 +1,151 @@
+use crate::{DatabaseTerm, MatchType, YomitanDatabase};
+use rusqlite::Error;
+use std::collections::HashSet;
+use wana_kana::{ConvertJapanese, IsJapaneseChar, IsJapaneseStr};
+
+//which rewrites of a query get searched along with the query itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NormalizeOptions {
+    //taberu searches for たべる, only when the whole query is romaji
+    pub romaji_to_kana: bool,
+    pub katakana_to_hiragana: bool,
+    pub hiragana_to_katakana: bool,
+    //ｔａｂｅｒｕ to taberu and ﾀﾍﾞﾙ to タベル
+    pub fold_width: bool,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        NormalizeOptions {
+            romaji_to_kana: true,
+            katakana_to_hiragana: true,
+            hiragana_to_katakana: true,
+            fold_width: true,
+        }
+    }
+}
+
+impl NormalizeOptions {
+    //searches for exactly what was typed
+    pub fn none() -> Self {
+        NormalizeOptions {
+            romaji_to_kana: false,
+            katakana_to_hiragana: false,
+            hiragana_to_katakana: false,
+            fold_width: false,
+        }
+    }
+}
+
+//halfwidth katakana from U+FF61 to U+FF9D, in order
+const HALFWIDTH_KATAKANA: &str =
+    "。「」、・ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン";
+
+//fullwidth ascii becomes ascii and halfwidth katakana becomes fullwidth,
+//with the separate dakuten and handakuten put back onto the kana before them
+pub fn fold_width(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '\u{ff01}'..='\u{ff5e}' => folded.push(char::from_u32(c as u32 - 0xfee0).unwrap_or(c)),
+            '\u{3000}' => folded.push(' '),
+            '\u{ff61}'..='\u{ff9d}' => {
+                let index = (c as u32 - 0xff61) as usize;
+                folded.push(HALFWIDTH_KATAKANA.chars().nth(index).unwrap_or(c));
+            }
+            '\u{ff9e}' | '\u{ff9f}' => {
+                let handakuten = c == '\u{ff9f}';
+                let combined = folded.chars().last().and_then(|base| voiced(base, handakuten));
+                match combined {
+                    Some(combined) => {
+                        folded.pop();
+                        folded.push(combined);
+                    }
+                    None => folded.push(if handakuten { '゜' } else { '゛' }),
+                }
+            }
+            _ => folded.push(c),
+        }
+    }
+
+    folded
+}
+
+//カ to ガ, ハ to バ or パ and so on
+fn voiced(base: char, handakuten: bool) -> Option<char> {
+    let code = base as u32;
+    match (base, handakuten) {
+        ('ウ', false) => Some('ヴ'),
+        //ッ sits between チ and ツ, so the voiced kana swap from odd to even there
+        ('カ'..='チ', false) if !code.is_multiple_of(2) => char::from_u32(code + 1),
+        ('ツ'..='ト', false) if code.is_multiple_of(2) => char::from_u32(code + 1),
+        ('ハ'..='ホ', false) if (code - 'ハ' as u32).is_multiple_of(3) => char::from_u32(code + 1),
+        ('ハ'..='ホ', true) if (code - 'ハ' as u32).is_multiple_of(3) => char::from_u32(code + 2),
+        _ => None,
+    }
+}
+
+//wana_kana's katakana_to_hiragana turns ー into a vowel (ラーメン to らあめん),
+//which no dictionary reading is written as, so this only shifts the kana
+fn katakana_to_hiragana(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'ァ'..='ヶ' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+//the query followed by every rewrite of it the options allow, without duplicates
+pub fn normalize_query(query: &str, options: NormalizeOptions) -> Vec<String> {
+    let mut variants = vec![query.to_string()];
+
+    let base = match options.fold_width {
+        true => fold_width(query),
+        false => query.to_string(),
+    };
+    variants.push(base.clone());
+
+    if options.romaji_to_kana && base.as_str().is_romaji() && base.chars().any(|c| c.is_ascii_alphabetic()) {
+        variants.push(base.to_lowercase().to_hiragana());
+    }
+
+    for variant in variants.clone() {
+        if options.katakana_to_hiragana && variant.chars().any(|c| c.is_katakana()) {
+            variants.push(katakana_to_hiragana(&variant));
+        }
+        if options.hiragana_to_katakana && variant.chars().any(|c| c.is_hiragana()) {
+            variants.push(wana_kana::utils::hiragana_to_katakana(&variant));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    variants.retain(|variant| !variant.is_empty() && seen.insert(variant.clone()));
+    variants
+}
+
+impl YomitanDatabase {
+    //lookup_terms for the query and each of its normalized forms. like lookup_terms
+    //this matches on both the expression and the reading, terms found by
+    //the query as typed come before the ones found through a rewrite
+    pub fn lookup_terms_normalized(
+        &self,
+        query: &str,
+        match_type: MatchType,
+        options: NormalizeOptions,
+    ) -> Result<Vec<DatabaseTerm>, Error> {
+        let mut seen_ids = HashSet::new();
+        let mut found = Vec::new();
+
+        for variant in normalize_query(query, options) {
+            for term in self.lookup_terms(&variant, match_type)? {
+                if seen_ids.insert(term.id) {
+                    found.push(term);
+                }
+            }
+        }
+
+        Ok(found)
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{DeinflectedTerm, YomitanDatabase};
+use rusqlite::Error;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+//how many characters after the cursor get looked at, same default as yomitan
+pub const MAX_SCAN_LENGTH: usize = 16;
+
+//a term found by scan, along with how much of the text it covered
+#[derive(Debug)]
+pub struct ScanMatch {
+    //characters of the source text this term was matched from, starting at the cursor
+    pub length: usize,
+    pub priority: i64,
+    pub term: DeinflectedTerm,
+}
+
+#[derive(Debug)]
+pub struct ScanResult {
+    //character offsets of the longest match in the scanned text, for highlighting
+    pub span: Range<usize>,
+    pub source: String,
+    //longest matches first, then by dictionary priority and the term's popularity
+    pub matches: Vec<ScanMatch>,
+}
+
+impl YomitanDatabase {
+    //finds the terms at a position in a line of text, like what's under the mouse in a texthooker.
+    //cursor_offset is in characters, not bytes. substrings from the cursor are tried from
+    //longest to shortest (stopping at whitespace), and every deinflected form of each one
+    //is looked up. returns None if nothing at the cursor is in any enabled dictionary
+    pub fn scan(&self, text: &str, cursor_offset: usize) -> Result<Option<ScanResult>, Error> {
+        let window: Vec<char> = text
+            .chars()
+            .skip(cursor_offset)
+            .take_while(|c| !c.is_whitespace())
+            .take(MAX_SCAN_LENGTH)
+            .collect();
+
+        let priorities = self.dictionary_priorities()?;
+        let mut seen_ids = HashSet::new();
+        let mut matches = Vec::new();
+
+        for length in (1..=window.len()).rev() {
+            let source: String = window[..length].iter().collect();
+
+            for term in self.lookup_deinflected(&source)? {
+                //a shorter substring can find a term that a longer one already did
+                if !seen_ids.insert(term.term.id) {
+                    continue;
+                }
+
+                matches.push(ScanMatch {
+                    length,
+                    priority: priorities.get(&term.term.dictionary).copied().unwrap_or_default(),
+                    term,
+                });
+            }
+        }
+
+        let Some(longest) = matches.iter().map(|found| found.length).max() else {
+            return Ok(None);
+        };
+
+        matches.sort_by_key(|found| {
+            (
+                Reverse(found.length),
+                found.priority,
+                Reverse(found.term.term.term.popularity),
+            )
+        });
+
+        Ok(Some(ScanResult {
+            span: cursor_offset..cursor_offset + longest,
+            source: window[..longest].iter().collect(),
+            matches,
+        }))
+    }
+
+    fn dictionary_priorities(&self) -> Result<HashMap<String, i64>, Error> {
+        let mut statement = self.connection.prepare_cached("SELECT title, priority FROM dictionaries")?;
+        let rows = statement.query_map([], |row| {
+            Ok((row.get(0)?, row.get::<_, Option<i64>>(1)?.unwrap_or_default()))
+        })?;
+        rows.collect()
+    }
+}
@@ -0,0 +1,87 @@
+use crate::{json_column, term_from_row, DatabaseTerm, YomitanDatabase, JOIN_DICTIONARIES, TERM_COLUMNS};
+use rusqlite::{Connection, Error};
+use schemas::*;
+
+//put around the matched words in GlossaryMatch::snippet
+pub const HIGHLIGHT_START: &str = "<mark>";
+pub const HIGHLIGHT_END: &str = "</mark>";
+
+//a term whose definitions matched a full text search
+#[derive(Debug)]
+pub struct GlossaryMatch {
+    pub term: DatabaseTerm,
+    //bm25 score from sqlite, lower is a better match
+    pub rank: f64,
+    //the part of the definitions around the match, with the matched words highlighted
+    pub snippet: String,
+}
+
+//the words in a term's definitions, which is what the full text index is built from.
+//readings in ruby are left out so they don't get in between the words of a sentence,
+//and text from different nodes is kept apart so it doesn't merge into one token
+pub(crate) fn glossary_text(definitions: &[TermDefinition]) -> String {
+    let options = PlainTextOptions {
+        furigana: Furigana::Dropped,
+        word_breaks: true,
+    };
+
+    definitions
+        .iter()
+        .map(|definition| definition.to_plain_text_with(options))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+//fills termsFts from the glossaries already in terms, for migrations
+pub(crate) fn index_existing_glossaries(conn: &Connection) -> Result<(), Error> {
+    let mut select = conn.prepare("SELECT id, glossary FROM terms")?;
+    let mut insert = conn.prepare("INSERT INTO termsFts (rowid, glossary) VALUES (?1, ?2)")?;
+
+    let mut rows = select.query([])?;
+    while let Some(row) = rows.next()? {
+        let id: i64 = row.get("id")?;
+        //a glossary that can't be read fails the migration instead of going unsearchable
+        let definitions: Vec<TermDefinition> = json_column(row, "glossary")?;
+        insert.execute((id, glossary_text(&definitions)))?;
+    }
+    Ok(())
+}
+
+//every word gets quoted so that things like "don't" or "AND" in the query
+//are searched for as they are instead of being read as fts5 syntax
+fn fts_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+impl YomitanDatabase {
+    //finds terms whose definitions contain every word of the query, like "umbrella"
+    //to find 傘. the best matches come first, at most limit of them
+    pub fn search_glossary(&self, query: &str, limit: usize) -> Result<Vec<GlossaryMatch>, Error> {
+        let query = fts_query(query);
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let sql = format!(
+            "SELECT {}, bm25(termsFts) AS rank, snippet(termsFts, 0, ?2, ?3, '…', 12) AS snippet \
+             FROM termsFts JOIN terms ON terms.id = termsFts.rowid {} \
+             WHERE termsFts MATCH ?1 AND dictionaries.enabled \
+             ORDER BY rank, dictionaries.priority, terms.id LIMIT ?4",
+            TERM_COLUMNS, JOIN_DICTIONARIES
+        );
+
+        let mut statement = self.connection.prepare_cached(&sql)?;
+        let rows = statement.query_map((query, HIGHLIGHT_START, HIGHLIGHT_END, limit as i64), |row| {
+            Ok(GlossaryMatch {
+                term: term_from_row(row)?,
+                rank: row.get("rank")?,
+                snippet: row.get("snippet")?,
+            })
+        })?;
+        rows.collect()
+    }
+}
@@ -0,0 +1,63 @@
+use crate::{DatabaseTerm, YomitanDatabase};
+use rusqlite::{Error, OptionalExtension};
+use schemas::TagInformation;
+
+//tags of a term expanded into the full records from the dictionary's tag banks
+#[derive(Debug)]
+pub struct ResolvedTermTags {
+    pub definition_tags: Vec<TagInformation>,
+    pub term_tags: Vec<TagInformation>,
+}
+
+impl YomitanDatabase {
+    pub fn lookup_tag(&self, dictionary: &str, name: &str) -> Result<Option<TagInformation>, Error> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT category, name, notes, sortOrder, score FROM tagMeta WHERE dictionary = ?1 AND name = ?2",
+        )?;
+
+        statement
+            .query_row((dictionary, name), |row| {
+                Ok(TagInformation {
+                    name: row.get("name")?,
+                    category: row.get("category")?,
+                    sorting_order: row.get("sortOrder")?,
+                    notes: row.get("notes")?,
+                    popularity_score: row.get("score")?,
+                })
+            })
+            .optional()
+    }
+
+    //expands a space separated tag string, like the ones in term banks.
+    //tags the dictionary doesn't define still show up, just with an empty category and notes.
+    //the result is sorted by the tags' sorting order, ties keep the order they were written in
+    pub fn resolve_tags(&self, dictionary: &str, tags: &str) -> Result<Vec<TagInformation>, Error> {
+        let mut resolved = Vec::new();
+
+        for name in tags.split_whitespace() {
+            let tag = match self.lookup_tag(dictionary, name)? {
+                Some(tag) => tag,
+                None => TagInformation {
+                    name: name.to_string(),
+                    category: String::new(),
+                    sorting_order: 0,
+                    notes: String::new(),
+                    popularity_score: 0,
+                },
+            };
+            resolved.push(tag);
+        }
+
+        resolved.sort_by_key(|tag| tag.sorting_order);
+        Ok(resolved)
+    }
+
+    pub fn resolve_term_tags(&self, term: &DatabaseTerm) -> Result<ResolvedTermTags, Error> {
+        let definition_tags = term.term.definition_tags.as_deref().unwrap_or_default();
+
+        Ok(ResolvedTermTags {
+            definition_tags: self.resolve_tags(&term.dictionary, definition_tags)?,
+            term_tags: self.resolve_tags(&term.dictionary, &term.term.term_tags)?,
+        })
+    }
+}
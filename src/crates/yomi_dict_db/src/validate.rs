@@ -0,0 +1,225 @@
+use crate::import::read_json;
+use crate::{collect_image_paths, list_banks, BankFile, BankKind, ImportError};
+use schemas::*;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::Path;
+use zip::ZipArchive;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    //the archive can't be imported as it is
+    Error,
+    //the archive imports, but something in it is probably a mistake
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: String,
+    //where in the file the problem is, like [12][5][0].content.
+    //empty when it's about the whole file
+    pub path: String,
+    //the bank entry the problem is in
+    pub entry: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: {}", severity, self.file)?;
+        if !self.path.is_empty() {
+            write!(f, " at {}", self.path)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+//everything validate_archive found, in the order the files were checked
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ValidationReport {
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|diagnostic| diagnostic.severity == Severity::Warning)
+    }
+
+    //whether import_archive would accept the archive, warnings don't count
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+}
+
+struct Validator {
+    report: ValidationReport,
+    format: Format,
+    //names from the tag banks and the index's tag_meta
+    tags: HashSet<String>,
+    //(expression, reading, sequence) to where it was first seen
+    sequences: HashMap<(String, String, i32), (String, usize)>,
+    //every file in the archive, to tell if the images terms point at are there
+    files: HashSet<String>,
+}
+
+impl Validator {
+    fn push(&mut self, severity: Severity, file: &str, path: String, entry: Option<usize>, message: String) {
+        self.report.diagnostics.push(Diagnostic {
+            severity,
+            file: file.to_string(),
+            path,
+            entry,
+            message,
+        });
+    }
+
+    //reads a bank entry by entry, the ones that parse are handed to check along with
+    //their index and the ones that don't are recorded as errors
+    fn each_entry<T, I, F>(&mut self, file: &str, bank: I, mut check: F)
+    where
+        I: Iterator<Item = Result<T, BankError>>,
+        F: FnMut(&mut Self, usize, T),
+    {
+        for (entry, parsed) in bank.enumerate() {
+            match parsed {
+                Ok(parsed) => check(self, entry, parsed),
+                Err(err) => self.push(Severity::Error, file, err.path.clone(), err.entry, err.message()),
+            }
+        }
+    }
+
+    fn check_tags(&mut self, file: &str, entry: usize, field: usize, tags: &str) {
+        for tag in tags.split_whitespace() {
+            if !self.tags.contains(tag) {
+                let path = format!("[{}][{}]", entry, field);
+                self.push(Severity::Warning, file, path, Some(entry), format!("unknown tag \"{}\"", tag));
+            }
+        }
+    }
+
+    fn check_term(&mut self, file: &str, entry: usize, term: &TermInformation) {
+        if let Some(definition_tags) = &term.definition_tags {
+            self.check_tags(file, entry, 2, definition_tags);
+        }
+        self.check_tags(file, entry, 7, &term.term_tags);
+
+        let mut paths = BTreeSet::new();
+        collect_image_paths(&term.definitions, &mut paths);
+        for path in paths {
+            if !self.files.contains(&path) {
+                let message = format!("image \"{}\" is not in the archive", path);
+                self.push(Severity::Warning, file, format!("[{}][5]", entry), Some(entry), message);
+            }
+        }
+
+        //terms share a sequence number on purpose so yomitan can merge them,
+        //the same term and reading showing up twice under one is the mistake
+        if term.sequence_number > 0 {
+            let key = (term.term.clone(), term.reading.clone(), term.sequence_number);
+            match self.sequences.get(&key) {
+                Some((first_file, first_entry)) => {
+                    let message = format!(
+                        "sequence number {} is used for {} ({}) more than once, first in {} at [{}]",
+                        term.sequence_number, term.term, term.reading, first_file, first_entry
+                    );
+                    self.push(Severity::Warning, file, format!("[{}][6]", entry), Some(entry), message);
+                }
+                None => {
+                    self.sequences.insert(key, (file.to_string(), entry));
+                }
+            }
+        }
+    }
+
+    fn check_bank<R: Read + Seek>(&mut self, archive: &mut ZipArchive<R>, kind: BankKind, file: &str) -> Result<(), ImportError> {
+        let entries = archive.by_name(file)?;
+        match kind {
+            BankKind::Tag => self.each_entry(file, TagBankReader::new(entries), |validator, _, tag| {
+                validator.tags.insert(tag.name);
+            }),
+            BankKind::Term => self.each_entry(file, read_term_bank(entries, &self.format), |validator, entry, term| {
+                validator.check_term(file, entry, &term)
+            }),
+            BankKind::TermMeta => self.each_entry(file, TermMetaBankReader::new(entries), |_, _, _| ()),
+            BankKind::Kanji => self.each_entry(file, read_kanji_bank(entries, &self.format), |validator, entry, kanji| {
+                validator.check_tags(file, entry, 3, &kanji.tags)
+            }),
+            BankKind::KanjiMeta => self.each_entry(file, KanjiMetaBankReader::new(entries), |_, _, _| ()),
+        }
+        Ok(())
+    }
+}
+
+//banks of each kind should be numbered 1, 2, 3 and so on without skipping any
+fn check_bank_numbers(banks: &[BankFile], validator: &mut Validator) {
+    let mut numbers: BTreeMap<BankKind, Vec<u32>> = BTreeMap::new();
+    for bank in banks {
+        numbers.entry(bank.kind).or_default().push(bank.number);
+    }
+
+    for (kind, present) in numbers {
+        let last = present.iter().copied().max().unwrap_or_default();
+        for number in 1..last {
+            if !present.contains(&number) {
+                let file = format!("{}_{}.json", kind.prefix(), number);
+                let message = format!("missing, the {} files go up to {}", kind.prefix(), last);
+                validator.push(Severity::Warning, &file, String::new(), None, message);
+            }
+        }
+    }
+}
+
+//checks every file of a dictionary archive without importing it. unlike an import this
+//doesn't stop at the first problem, every bad entry of every bank is reported.
+//the Err case is only for archives that can't be opened at all
+pub fn validate_archive<P: AsRef<Path>>(path: P) -> Result<ValidationReport, ImportError> {
+    let file = File::open(path)?;
+    let mut archive = ZipArchive::new(file)?;
+
+    let mut validator = Validator {
+        report: ValidationReport::default(),
+        format: Format::V3,
+        tags: HashSet::new(),
+        sequences: HashMap::new(),
+        files: archive.file_names().map(String::from).collect(),
+    };
+
+    if archive.index_for_name("index.json").is_none() {
+        let message = String::from("the archive does not contain an index.json");
+        validator.push(Severity::Error, "index.json", String::new(), None, message);
+    } else {
+        match read_json::<DictionaryIndex, _>(&mut archive, "index.json") {
+            Ok(index) => {
+                validator.format = index.format.or(index.version).unwrap_or(Format::V3);
+                if let Some(tag_meta) = index.tag_meta {
+                    validator.tags.extend(tag_meta.into_keys());
+                }
+            }
+            Err(ImportError::Json { file, path, source }) => {
+                validator.push(Severity::Error, &file, path, None, source.to_string());
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    //tag banks come first, so every tag is known by the time the terms get checked
+    let banks = list_banks(&archive);
+    check_bank_numbers(&banks, &mut validator);
+    for bank in &banks {
+        validator.check_bank(&mut archive, bank.kind, &bank.name)?;
+    }
+
+    Ok(validator.report)
+}
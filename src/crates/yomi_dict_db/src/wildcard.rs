@@ -0,0 +1,132 @@
+use crate::{term_from_row, DatabaseTerm, YomitanDatabase, JOIN_DICTIONARIES, TERM_COLUMNS};
+use rusqlite::Error;
+use std::fmt;
+
+//what to do with a pattern like *べる in dictionaries that don't have prefix_wildcards_supported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeadingWildcard {
+    //fail with WildcardError::LeadingWildcardUnsupported
+    Reject,
+    //match against every term of those dictionaries, which can't use an index and is slow
+    Scan,
+}
+
+#[derive(Debug)]
+pub enum WildcardError {
+    Database(Error),
+    //titles of the enabled dictionaries that can't search with a leading wildcard
+    LeadingWildcardUnsupported(Vec<String>),
+}
+
+impl fmt::Display for WildcardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WildcardError::Database(err) => write!(f, "database error: {}", err),
+            WildcardError::LeadingWildcardUnsupported(titles) => write!(
+                f,
+                "leading wildcards are not supported by {}",
+                titles.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WildcardError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WildcardError::Database(err) => Some(err),
+            WildcardError::LeadingWildcardUnsupported(_) => None,
+        }
+    }
+}
+
+impl From<Error> for WildcardError {
+    fn from(err: Error) -> Self {
+        WildcardError::Database(err)
+    }
+}
+
+//one page of wildcard search results
+#[derive(Debug)]
+pub struct WildcardPage {
+    pub terms: Vec<DatabaseTerm>,
+    pub offset: usize,
+    //whether there are more results after this page
+    pub has_more: bool,
+}
+
+//* and ? are kept as wildcards, [ is the only other character GLOB treats specially
+fn wildcard_to_glob(pattern: &str) -> String {
+    pattern.replace('[', "[[]")
+}
+
+impl YomitanDatabase {
+    //matches expressions and readings against a pattern where * is any number of
+    //characters and ? is exactly one. a pattern starting with a wildcard is matched
+    //against the reversed columns of dictionaries that support it, so it can still
+    //use an index. results are in dictionary priority order, limit at a time
+    pub fn search_wildcard(
+        &self,
+        pattern: &str,
+        leading: LeadingWildcard,
+        limit: usize,
+        offset: usize,
+    ) -> Result<WildcardPage, WildcardError> {
+        let forward = wildcard_to_glob(pattern);
+
+        //one extra row is fetched to tell if there's another page
+        let sql = if pattern.starts_with(['*', '?']) {
+            if leading == LeadingWildcard::Reject {
+                let unsupported = self.dictionaries_without_prefix_wildcards()?;
+                if !unsupported.is_empty() {
+                    return Err(WildcardError::LeadingWildcardUnsupported(unsupported));
+                }
+            }
+
+            format!(
+                "SELECT * FROM (\
+                 SELECT {columns}, dictionaries.priority AS priority FROM terms {join} \
+                 WHERE dictionaries.enabled AND dictionaries.prefix_wildcards_supported \
+                 AND (terms.expressionReverse GLOB ?2 OR terms.readingReverse GLOB ?2) \
+                 UNION ALL \
+                 SELECT {columns}, dictionaries.priority AS priority FROM terms {join} \
+                 WHERE dictionaries.enabled AND NOT COALESCE(dictionaries.prefix_wildcards_supported, 0) \
+                 AND (terms.expression GLOB ?1 OR terms.reading GLOB ?1)) \
+                 ORDER BY priority, id LIMIT ?3 OFFSET ?4",
+                columns = TERM_COLUMNS,
+                join = JOIN_DICTIONARIES
+            )
+        } else {
+            format!(
+                "SELECT {} FROM terms {} \
+                 WHERE dictionaries.enabled AND (terms.expression GLOB ?1 OR terms.reading GLOB ?1) \
+                 ORDER BY dictionaries.priority, terms.id LIMIT ?3 OFFSET ?4",
+                TERM_COLUMNS, JOIN_DICTIONARIES
+            )
+        };
+        let reversed = wildcard_to_glob(&pattern.chars().rev().collect::<String>());
+
+        let mut statement = self.connection.prepare_cached(&sql)?;
+        let mut terms = statement
+            .query_map((&forward, &reversed, limit as i64 + 1, offset as i64), term_from_row)?
+            .collect::<Result<Vec<DatabaseTerm>, Error>>()?;
+
+        let has_more = terms.len() > limit;
+        terms.truncate(limit);
+
+        Ok(WildcardPage {
+            terms,
+            offset,
+            has_more,
+        })
+    }
+
+    fn dictionaries_without_prefix_wildcards(&self) -> Result<Vec<String>, Error> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT title FROM dictionaries \
+             WHERE enabled AND NOT COALESCE(prefix_wildcards_supported, 0) ORDER BY priority, id",
+        )?;
+        let rows = statement.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
+}
@@ -3,10 +3,43 @@ use schemas::*;
 use rusqlite::{params, types::ToSqlOutput, Connection, Error, ToSql};
 use std::result::Result;
 
+pub mod deinflect;
+pub mod dictionaries;
+pub mod frequency;
+pub mod import;
+pub mod lookup;
+pub mod media;
+pub mod migrations;
+pub mod normalize;
+pub mod scan;
+pub mod search;
+pub mod tags;
+pub mod validate;
+pub mod wildcard;
+
+pub use deinflect::*;
+pub use dictionaries::*;
+pub use import::*;
+pub use lookup::*;
+pub use media::*;
+pub use migrations::{schema_version, OpenError, SCHEMA_VERSION};
+pub use normalize::*;
+pub use scan::*;
+pub use search::*;
+pub use tags::*;
+pub use validate::*;
+pub use wildcard::*;
+
 pub struct YomitanDatabase {
     connection: Connection,
 }
 
+//json for a column, a value that can't be serialized fails the insert
+//instead of leaving an empty string in the column
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String, Error> {
+    serde_json::to_string(value).map_err(|err| Error::ToSqlConversionFailure(Box::new(err)))
+}
+
 struct FormatWrapper(Format);
 
 impl ToSql for FormatWrapper {
@@ -21,41 +54,62 @@ impl ToSql for FormatWrapper {
 
 impl YomitanDatabase {
     //opens an existing database or create a new one
-    pub fn open_database<P: AsRef<Path>>(dict: P) -> Result<YomitanDatabase, Error> {
+    pub fn open_database<P: AsRef<Path>>(dict: P) -> Result<YomitanDatabase, OpenError> {
         let connection= Connection::open(dict)?;
         
-        //create tables if this is a new database
-        create_tables(&connection)?;
+        //create tables if this is a new database, or upgrade an older one.
+        //databases from a newer version of the library are refused
+        migrations::migrate(&connection)?;
 
         Ok(YomitanDatabase { connection })
     }
 
-    //gui opens zip file and sends the files individually over
-    //this is done because otherwise im not sure how else to display progress xdxd
-    //ui checks for if index.json is present and if the banks are numbered appropriately
+    //import_archive opens the zip and reports progress itself,
+    //this is still here for anything that wants to send the files over individually
     pub fn insert_index(&self, index: &DictionaryIndex, prefix_wildcards_support: bool) -> Result<(), Error> {
         let mut format: Option<FormatWrapper> = None;
-        if let Some(version) = &index.format {
+        if let Some(version) = index.format.as_ref().or(index.version.as_ref()) {
             format = Some(FormatWrapper(version.clone()));
         }
 
         match self.connection.execute(
-            "INSERT INTO dictionaries (title, version, revision, import_date, prefix_wildcards_supported, sequenced, counts) \
-                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-                (
+            "INSERT INTO dictionaries (title, version, revision, import_date, prefix_wildcards_supported, sequenced, counts, \
+                source_language, target_language, frequency_mode, minimum_yomitan_version, priority) \
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, (SELECT COALESCE(MAX(priority) + 1, 0) FROM dictionaries))",
+                params![
                     &index.title,
                     format,
                     &index.revision,
                     chrono::offset::Local::now(),
                     prefix_wildcards_support,
                     &index.sequenced,
-                    0
-                )
+                    0,
+                    index.source_language.as_ref().map(|language| language.to_string()),
+                    index.target_language.as_ref().map(|language| language.to_string()),
+                    index.frequency_mode.map(|mode| mode.to_string()),
+                    &index.minimum_yomitan_version
+                ]
         ) {
             Err(err) => return Err(err),
             Ok(_) => println!("Inserted index.json"),
         }
 
+        //older dictionaries define their tags in the index instead of in tag banks
+        if let Some(tag_meta) = &index.tag_meta {
+            let mut names: Vec<&String> = tag_meta.keys().collect();
+            names.sort();
+            for name in names {
+                let info = &tag_meta[name];
+                self.insert_tag(TagInformation {
+                    name: name.clone(),
+                    category: info.category.clone().unwrap_or_default(),
+                    sorting_order: info.order.unwrap_or_default(),
+                    notes: info.notes.clone().unwrap_or_default(),
+                    popularity_score: info.score.unwrap_or_default(),
+                }, &index.title)?;
+            }
+        }
+
         Ok(())
     }
 
@@ -65,115 +119,179 @@ impl YomitanDatabase {
             
 
         
-            self.connection.execute(INSERT_QUERY, params![term.definition_tags,
+            self.connection.prepare_cached(INSERT_QUERY)?.execute(params![term.definition_tags.as_deref().unwrap_or_default(),
                                                             dictionary,
                                                             term.term,
                                                             term.term.chars().rev().collect::<String>(),
-                                                            term.definitions_as_json(),
+                                                            to_json(&term.definitions)?,
                                                             term.reading,
                                                             term.reading.chars().rev().collect::<String>(),
                                                             term.deinflectors,
                                                             term.popularity,
                                                             term.sequence_number,
                                                             term.term_tags])?;
+
+        //the full text index shares its rowid with the term
+        self.connection
+            .prepare_cached("INSERT INTO termsFts (rowid, glossary) VALUES (?1, ?2)")?
+            .execute((self.connection.last_insert_rowid(), search::glossary_text(&term.definitions)))?;
         Ok(())
     }
-}
 
-fn create_tables(conn: &Connection) -> Result<(), Error> {
-    match conn.execute(
-        "CREATE TABLE IF NOT EXISTS dictionaries( 
-                id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL, 
-                title TEXT NOT NULL, 
-                version INTEGER, 
-                revision TEXT NOT NULL, 
-                import_date DATE, 
-                prefix_wildcards_supported BOOLEAN, 
-                sequenced BOOLEAN, 
-                counts INTEGER DEFAULT 0)",
-    (),
-    ) {
-        Err(err) => return Err(err),
-        Ok(_) => println!("Created dictionaries table"),
+    pub fn insert_term_meta(&self, meta: TermMeta, dictionary: &str) -> Result<(), Error> {
+        const INSERT_QUERY: &str = "INSERT INTO termMeta (dictionary, data, expression, mode) VALUES (?1, ?2, ?3, ?4)";
+
+        //data is stored as the json of the third element of the meta entry,
+        //its shape depends on the mode
+        let (expression, mode, data) = match meta {
+            TermMeta::Frequency(freq) => (freq.expression, freq.mode, to_json(&freq.data)?),
+            TermMeta::Pitch(pitch) => (pitch.expression, pitch.mode, to_json(&pitch.data)?),
+            TermMeta::Phonetic(phonetic) => (phonetic.expression, phonetic.mode, to_json(&phonetic.data)?),
+        };
+
+        self.connection.prepare_cached(INSERT_QUERY)?.execute(params![dictionary,
+                                                        data,
+                                                        expression,
+                                                        mode])?;
+        Ok(())
     }
 
-    match conn.execute(
-        "CREATE TABLE IF NOT EXISTS kanji(
-             id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
-             dictionary TEXT NOT NULL,
-             kunyomi TEXT NOT NULL,
-             meanings TEXT NOT NULL,
-             onyomi TEXT NOT NULL,
-             stats TEXT NOT NULL,
-             tag TEXT NOT NULL)",
-    (),
-    ) {
-        Err(err) => return Err(err),
-        Ok(_) => println!("Created kanji table"),
+    pub fn insert_kanji(&self, kanji: KanjiInformation, dictionary: &str) -> Result<(), Error> {
+        const INSERT_QUERY: &str = "INSERT INTO kanji (character, dictionary, kunyomi, meanings, onyomi, stats, tag) \
+                                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)";
+
+        self.connection.prepare_cached(INSERT_QUERY)?.execute(params![kanji.kanji,
+                                                        dictionary,
+                                                        kanji.kunyomi.join(" "),
+                                                        to_json(&kanji.meanings)?,
+                                                        kanji.onyomi.join(" "),
+                                                        to_json(&kanji.stats)?,
+                                                        kanji.tags])?;
+        Ok(())
     }
 
-    match conn.execute(
-        "CREATE TABLE IF NOT EXISTS media(
-             key INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
-             id INTEGER NOT NULL,
-             dictionary TEXT NOT NULL,
-             height INTEGER NOT NULL,
-             width INTEGER NOT NULL,
-             mediatype TEXT NOT NULL,
-             path TEXT NOT NULL,
-             content BLOB NOT NULL)",
-    (),
-    ) {
-        Err(err) => return Err(err),
-        Ok(_) => println!("Created media table"),
+    pub fn insert_kanji_meta(&self, meta: KanjiMetaData, dictionary: &str) -> Result<(), Error> {
+        const INSERT_QUERY: &str = "INSERT INTO kanjiMeta (character, dictionary, data, mode) VALUES (?1, ?2, ?3, ?4)";
+
+        self.connection.prepare_cached(INSERT_QUERY)?.execute(params![meta.character,
+                                                        dictionary,
+                                                        to_json(&meta.data)?,
+                                                        meta.mode])?;
+        Ok(())
     }
 
-    match conn.execute(
-        "CREATE TABLE IF NOT EXISTS tagMeta( \
-             id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL, \
-             dictionary TEXT NOT NULL, \
-             category TEXT NOT NULL, \
-             name TEXT NOT NULL, \
-             notes TEXT NOT NULL, \
-             sortOrder INTEGER NOT NULL, \
-             score INTEGER NOT NULL)",
-    (),
-    ) {
-        Err(err) => return Err(err),
-        Ok(_) => println!("Created tagMeta table"),
+    pub fn insert_tag(&self, tag: TagInformation, dictionary: &str) -> Result<(), Error> {
+        const INSERT_QUERY: &str = "INSERT INTO tagMeta (dictionary, category, name, notes, sortOrder, score) \
+                                    VALUES (?1, ?2, ?3, ?4, ?5, ?6)";
+
+        self.connection.prepare_cached(INSERT_QUERY)?.execute(params![dictionary,
+                                                        tag.category,
+                                                        tag.name,
+                                                        tag.notes,
+                                                        tag.sorting_order,
+                                                        tag.popularity_score])?;
+        Ok(())
+    }
+
+    //bulk inserts, each bank is written inside of its own savepoint so a bank is either
+    //fully inserted or not at all. returns the number of rows inserted
+    pub fn insert_term_bank<I>(&self, bank: I, dictionary: &str) -> Result<usize, Error>
+    where
+        I: IntoIterator<Item = TermInformation>,
+    {
+        self.with_savepoint("term_bank", || {
+            let mut count = 0;
+            for term in bank {
+                self.insert_term(term, dictionary)?;
+                count += 1;
+            }
+            Ok(count)
+        })
+    }
+
+    pub fn insert_term_meta_bank<I>(&self, bank: I, dictionary: &str) -> Result<usize, Error>
+    where
+        I: IntoIterator<Item = TermMeta>,
+    {
+        self.with_savepoint("term_meta_bank", || {
+            let mut count = 0;
+            for meta in bank {
+                self.insert_term_meta(meta, dictionary)?;
+                count += 1;
+            }
+            Ok(count)
+        })
+    }
+
+    pub fn insert_kanji_bank<I>(&self, bank: I, dictionary: &str) -> Result<usize, Error>
+    where
+        I: IntoIterator<Item = KanjiInformation>,
+    {
+        self.with_savepoint("kanji_bank", || {
+            let mut count = 0;
+            for kanji in bank {
+                self.insert_kanji(kanji, dictionary)?;
+                count += 1;
+            }
+            Ok(count)
+        })
+    }
+
+    pub fn insert_kanji_meta_bank<I>(&self, bank: I, dictionary: &str) -> Result<usize, Error>
+    where
+        I: IntoIterator<Item = KanjiMetaData>,
+    {
+        self.with_savepoint("kanji_meta_bank", || {
+            let mut count = 0;
+            for meta in bank {
+                self.insert_kanji_meta(meta, dictionary)?;
+                count += 1;
+            }
+            Ok(count)
+        })
     }
 
-    match conn.execute(
-        "CREATE TABLE IF NOT EXISTS termMeta(
-             id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
-             dictionary TEXT NOT NULL,
-             data TEXT NOT NULL,
-             expression TEXT NOT NULL,
-             mode TEXT NOT NULL)",
-    (),
-    ) {
-        Err(err) => return Err(err),
-        Ok(_) => println!("Created termMeta table"),
+    pub fn insert_tag_bank<I>(&self, bank: I, dictionary: &str) -> Result<usize, Error>
+    where
+        I: IntoIterator<Item = TagInformation>,
+    {
+        self.with_savepoint("tag_bank", || {
+            let mut count = 0;
+            for tag in bank {
+                self.insert_tag(tag, dictionary)?;
+                count += 1;
+            }
+            Ok(count)
+        })
+    }
+
+    //runs f inside of a savepoint, everything written by f is rolled back if it returns an error.
+    //savepoints nest, so a bank can be inserted while a whole dictionary import is in progress,
+    //and outside of any transaction it behaves like BEGIN/COMMIT
+    pub(crate) fn with_savepoint<T, E, F>(&self, name: &str, f: F) -> Result<T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+        E: From<Error>,
+    {
+        self.connection.execute_batch(&format!("SAVEPOINT {}", name))?;
+
+        match f() {
+            Ok(value) => {
+                self.connection.execute_batch(&format!("RELEASE {}", name))?;
+                Ok(value)
+            }
+            Err(err) => {
+                self.connection.execute_batch(&format!("ROLLBACK TO {0}; RELEASE {0}", name))?;
+                Err(err)
+            }
+        }
     }
 
-    match conn.execute(
-        "CREATE TABLE IF NOT EXISTS terms(
-             id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
-             definitionTags TEXT NOT NULL,
-             dictionary TEXT NOT NULL,
-             expression TEXT NOT NULL,
-             expressionReverse TEXT NOT NULL,
-             glossary TEXT NOT NULL,
-             reading TEXT NOT NULL,
-             readingReverse TEXT NOT NULL,
-             rules TEXT NOT NULL,
-             score INTEGER NOT NULL,
-             sequence INTEGER NOT NULL,
-             termTags TEXT NOT NULL)",
-    (),
-    ) {
-        Err(err) => return Err(err),
-        Ok(_) => println!("Created terms table"),
+    pub fn dictionary_exists(&self, title: &str) -> Result<bool, Error> {
+        self.connection.query_row(
+            "SELECT EXISTS(SELECT 1 FROM dictionaries WHERE title = ?1)",
+            [title],
+            |row| row.get(0),
+        )
     }
-    Ok(())
 }
@@ -0,0 +1,55 @@
+//helpers shared by the integration tests, not every test uses all of them
+#![allow(dead_code)]
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use yomi_dict_db::YomitanDatabase;
+use zip::write::SimpleFileOptions;
+
+//a file in the temp directory that gets deleted when the test is done with it
+pub struct TempFile(PathBuf);
+
+impl TempFile {
+    pub fn new(name: &str) -> TempFile {
+        let path = std::env::temp_dir().join(format!("yomi_dict_db_test_{}_{}", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        TempFile(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+//a dictionary archive with the files given as (name, content)
+pub fn write_archive(name: &str, files: &[(&str, &str)]) -> TempFile {
+    let archive = TempFile::new(name);
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(archive.path()).unwrap());
+    for (file, content) in files {
+        zip.start_file(*file, SimpleFileOptions::default()).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+    archive
+}
+
+//a term bank of count nouns, 語{n} read ご{n}
+pub fn term_bank(start: usize, count: usize) -> String {
+    let terms: Vec<String> = (start..start + count)
+        .map(|i| format!(r#"["語{0}","ご{0}","","",0,["word {0}"],{0},""]"#, i))
+        .collect();
+    format!("[{}]", terms.join(","))
+}
+
+//a new database in the temp directory, the TempFile has to outlive the database
+pub fn open_database(name: &str) -> (TempFile, YomitanDatabase) {
+    let database = TempFile::new(name);
+    let db = YomitanDatabase::open_database(database.path()).unwrap();
+    (database, db)
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::{open_database, term_bank, write_archive, TempFile};
+use yomi_dict_db::{validate_archive, ImportError, ImportOptions, ImportPhase, YomitanDatabase};
+
+#[test]
+fn index_with_format_and_version() {
+    let archive = write_archive(
+        "format_and_version.zip",
+        &[
+            ("index.json", r#"{"title":"Both","revision":"1","format":3,"version":3}"#),
+            ("term_bank_1.json", r#"[["読む","よむ","","v5",0,["to read"],1,""]]"#),
+        ],
+    );
+
+    let report = validate_archive(archive.path()).unwrap();
+    assert!(report.is_valid(), "{:?}", report.diagnostics);
+
+    let (_database, db) = open_database("format_and_version.db");
+    let summary = db.import_archive(archive.path()).unwrap();
+    assert_eq!(summary.terms, 1);
+}
+
+//whether the import dropped the indexes and built them again at the end
+fn import_rebuilds_indexes(db: &YomitanDatabase, archive: &TempFile) -> bool {
+    let mut rebuilt = false;
+    db.import_archive_with_progress(archive.path(), |progress| {
+        rebuilt |= progress.phase == ImportPhase::Indexing;
+    })
+    .unwrap();
+    rebuilt
+}
+
+#[test]
+fn small_import_keeps_indexes() {
+    let big_bank = term_bank(0, 200);
+    let big = write_archive(
+        "keeps_indexes_big.zip",
+        &[
+            ("index.json", r#"{"title":"Big","revision":"1","format":3}"#),
+            ("term_bank_1.json", &big_bank),
+        ],
+    );
+    let small = write_archive(
+        "keeps_indexes_small.zip",
+        &[
+            ("index.json", r#"{"title":"Small","revision":"1","format":3}"#),
+            ("term_bank_1.json", r#"[["読む","よむ","","v5",0,["to read"],1,""]]"#),
+        ],
+    );
+
+    let (_database, db) = open_database("keeps_indexes.db");
+
+    //the tables are empty, so the first import rebuilds them
+    assert!(import_rebuilds_indexes(&db, &big));
+    assert!(!import_rebuilds_indexes(&db, &small));
+    assert_eq!(db.lookup_term("読む", None).unwrap().len(), 1);
+}
+
+#[test]
+fn failed_parallel_import_leaves_nothing() {
+    //the second bank fails after more than a chunk of it was already inserted
+    let first = term_bank(0, 1500);
+    let second = term_bank(1500, 1500).replacen(r#"["語2000","ご2000","","",0,"#, r#"["語2000","ご2000","","","zero","#, 1);
+    let third = term_bank(3000, 1500);
+    let archive = write_archive(
+        "failed_parallel.zip",
+        &[
+            ("index.json", r#"{"title":"Broken","revision":"1","format":3}"#),
+            ("term_bank_1.json", &first),
+            ("term_bank_2.json", &second),
+            ("term_bank_3.json", &third),
+        ],
+    );
+
+    let (database, db) = open_database("failed_parallel.db");
+    let options = ImportOptions { workers: 3, queue: 1, ..ImportOptions::default() };
+    match db.import_archive_with_options(archive.path(), options, |_| {}) {
+        Err(ImportError::Bank { file, .. }) => assert_eq!(file, "term_bank_2.json"),
+        other => panic!("expected term_bank_2.json to fail, got {:?}", other),
+    }
+
+    assert!(db.list_dictionaries().unwrap().is_empty());
+    drop(db);
+
+    let connection = rusqlite::Connection::open(database.path()).unwrap();
+    for table in ["terms", "termsFts", "tagMeta", "media"] {
+        let rows: i64 = connection
+            .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 0, "{} should be empty", table);
+    }
+}
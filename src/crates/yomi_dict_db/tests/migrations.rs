@@ -0,0 +1,145 @@
+mod common;
+
+use common::{open_database, write_archive, TempFile};
+use schemas::TermDefinition;
+use yomi_dict_db::{schema_version, YomitanDatabase, SCHEMA_VERSION};
+
+#[test]
+fn glossary_search_is_rebuilt() {
+    let archive = write_archive(
+        "rebuild_fts.zip",
+        &[
+            ("index.json", r#"{"title":"Fts","revision":"1","format":3}"#),
+            ("term_bank_1.json", r#"[["傘","かさ","n","",0,["umbrella"],1,""]]"#),
+        ],
+    );
+
+    let (database, db) = open_database("rebuild_fts.db");
+    db.import_archive(archive.path()).unwrap();
+    drop(db);
+
+    //what a database migrated before the rebuild looks like, with text in termsFts
+    //that doesn't match what the definitions give now
+    let connection = rusqlite::Connection::open(database.path()).unwrap();
+    connection
+        .execute_batch("UPDATE termsFts SET glossary = 'stale'; PRAGMA user_version = 6;")
+        .unwrap();
+    drop(connection);
+
+    let db = YomitanDatabase::open_database(database.path()).unwrap();
+    assert!(db.search_glossary("stale", 10).unwrap().is_empty());
+    assert_eq!(db.search_glossary("umbrella", 10).unwrap().len(), 1);
+}
+
+//the tables as the library created them before databases had a version
+const BASELINE_TABLES: &str = "
+    CREATE TABLE dictionaries(
+        id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+        title TEXT NOT NULL,
+        version INTEGER,
+        revision TEXT NOT NULL,
+        import_date DATE,
+        prefix_wildcards_supported BOOLEAN,
+        sequenced BOOLEAN,
+        counts INTEGER DEFAULT 0);
+    CREATE TABLE kanji(
+        id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+        dictionary TEXT NOT NULL,
+        kunyomi TEXT NOT NULL,
+        meanings TEXT NOT NULL,
+        onyomi TEXT NOT NULL,
+        stats TEXT NOT NULL,
+        tag TEXT NOT NULL);
+    CREATE TABLE media(
+        key INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+        id INTEGER NOT NULL,
+        dictionary TEXT NOT NULL,
+        height INTEGER NOT NULL,
+        width INTEGER NOT NULL,
+        mediatype TEXT NOT NULL,
+        path TEXT NOT NULL,
+        content BLOB NOT NULL);
+    CREATE TABLE tagMeta(
+        id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+        dictionary TEXT NOT NULL,
+        category TEXT NOT NULL,
+        name TEXT NOT NULL,
+        notes TEXT NOT NULL,
+        sortOrder INTEGER NOT NULL,
+        score INTEGER NOT NULL);
+    CREATE TABLE termMeta(
+        id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+        dictionary TEXT NOT NULL,
+        data TEXT NOT NULL,
+        expression TEXT NOT NULL,
+        mode TEXT NOT NULL);
+    CREATE TABLE terms(
+        id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+        definitionTags TEXT NOT NULL,
+        dictionary TEXT NOT NULL,
+        expression TEXT NOT NULL,
+        expressionReverse TEXT NOT NULL,
+        glossary TEXT NOT NULL,
+        reading TEXT NOT NULL,
+        readingReverse TEXT NOT NULL,
+        rules TEXT NOT NULL,
+        score INTEGER NOT NULL,
+        sequence INTEGER NOT NULL,
+        termTags TEXT NOT NULL);
+
+    INSERT INTO dictionaries (title, version, revision, prefix_wildcards_supported, sequenced, counts)
+        VALUES ('Old', 3, '1', 1, 1, 1);
+    INSERT INTO terms (definitionTags, dictionary, expression, expressionReverse, glossary, reading,
+                       readingReverse, rules, score, sequence, termTags)
+        VALUES ('v5', 'Old', '読む', 'む読', '[\"to read\"]', 'よむ', 'むよ', 'v5', 0, 1, '');
+    INSERT INTO terms (definitionTags, dictionary, expression, expressionReverse, glossary, reading,
+                       readingReverse, rules, score, sequence, termTags)
+        VALUES ('', 'Old', '読んだ', 'だん読', '[{\"uninflected_term\":\"読む\",\"inflection_rules\":[\"past\"]}]',
+                'よんだ', 'だんよ', '', 0, 2, '');";
+
+#[test]
+fn unversioned_database_is_upgraded() {
+    let database = TempFile::new("baseline.db");
+
+    let connection = rusqlite::Connection::open(database.path()).unwrap();
+    connection.execute_batch(BASELINE_TABLES).unwrap();
+    drop(connection);
+
+    let db = YomitanDatabase::open_database(database.path()).unwrap();
+    let dictionaries = db.list_dictionaries().unwrap();
+    assert_eq!(dictionaries.len(), 1);
+    assert_eq!(dictionaries[0].title, "Old");
+
+    let terms = db.lookup_term("読む", Some("よむ")).unwrap();
+    assert_eq!(terms.len(), 1);
+    assert_eq!(terms[0].term.deinflectors, "v5");
+    assert_eq!(db.search_glossary("read", 10).unwrap().len(), 1);
+
+    //inflections used to be stored as maps
+    let terms = db.lookup_term("読んだ", None).unwrap();
+    assert_eq!(terms.len(), 1);
+    match &terms[0].term.definitions[0] {
+        TermDefinition::Inflection(inflection) => assert_eq!(inflection.uninflected_term, "読む"),
+        other => panic!("expected an inflection, got {:?}", other),
+    }
+    drop(db);
+
+    let connection = rusqlite::Connection::open(database.path()).unwrap();
+    assert_eq!(schema_version(&connection).unwrap(), SCHEMA_VERSION);
+}
+
+#[test]
+fn unreadable_glossary_fails_the_rebuild() {
+    let database = TempFile::new("bad_glossary.db");
+
+    let connection = rusqlite::Connection::open(database.path()).unwrap();
+    connection.execute_batch(BASELINE_TABLES).unwrap();
+    connection.execute_batch("UPDATE terms SET glossary = 'not json'").unwrap();
+    drop(connection);
+
+    assert!(YomitanDatabase::open_database(database.path()).is_err());
+
+    //the failed migration is rolled back, so the database is left as it was
+    let connection = rusqlite::Connection::open(database.path()).unwrap();
+    assert!(schema_version(&connection).unwrap() < SCHEMA_VERSION);
+}
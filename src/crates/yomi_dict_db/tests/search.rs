@@ -0,0 +1,28 @@
+mod common;
+
+use common::{open_database, write_archive};
+
+#[test]
+fn adjacent_inline_nodes_are_separate_words() {
+    //tag pills next to each other and an image right before a link, with no text between them
+    let glossary = r#"{"type": "structured-content", "content": [
+        {"tag": "span", "content": "noun"}, {"tag": "span", "content": "suru"},
+        {"tag": "img", "path": "img/umbrella.png", "alt": "picture"},
+        {"tag": "a", "href": "?query=雨", "content": "rain"}
+    ]}"#;
+    let bank = format!(r#"[["傘","かさ","","",0,[{}],1,""]]"#, glossary);
+
+    let archive = write_archive(
+        "inline_nodes.zip",
+        &[
+            ("index.json", r#"{"title":"Inline","revision":"1","format":3}"#),
+            ("term_bank_1.json", &bank),
+        ],
+    );
+
+    let (_database, db) = open_database("inline_nodes.db");
+    db.import_archive(archive.path()).unwrap();
+    for word in ["noun", "suru", "picture", "rain"] {
+        assert_eq!(db.search_glossary(word, 10).unwrap().len(), 1, "{} should be found", word);
+    }
+}
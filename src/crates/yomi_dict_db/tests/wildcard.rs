@@ -0,0 +1,55 @@
+mod common;
+
+use common::{open_database, write_archive, TempFile};
+use yomi_dict_db::{ImportOptions, LeadingWildcard, WildcardError, YomitanDatabase};
+
+//one dictionary imported with prefix wildcards and one without, both with a term ending in べる
+fn import_both(name: &str) -> (TempFile, YomitanDatabase) {
+    let supported = write_archive(
+        &format!("{}_supported.zip", name),
+        &[
+            ("index.json", r#"{"title":"Supported","revision":"1","format":3}"#),
+            ("term_bank_1.json", r#"[["食べる","たべる","","v1",0,["to eat"],1,""]]"#),
+        ],
+    );
+    let unsupported = write_archive(
+        &format!("{}_unsupported.zip", name),
+        &[
+            ("index.json", r#"{"title":"Unsupported","revision":"1","format":3}"#),
+            ("term_bank_1.json", r#"[["調べる","しらべる","","v1",0,["to investigate"],2,""]]"#),
+        ],
+    );
+
+    let (database, db) = open_database(&format!("{}.db", name));
+    db.import_archive(supported.path()).unwrap();
+    let options = ImportOptions {
+        prefix_wildcards: false,
+        ..ImportOptions::default()
+    };
+    db.import_archive_with_options(unsupported.path(), options, |_| {}).unwrap();
+    (database, db)
+}
+
+#[test]
+fn leading_wildcard_is_rejected() {
+    let (_database, db) = import_both("leading_reject");
+
+    match db.search_wildcard("*べる", LeadingWildcard::Reject, 10, 0) {
+        Err(WildcardError::LeadingWildcardUnsupported(titles)) => assert_eq!(titles, ["Unsupported"]),
+        other => panic!("expected Unsupported to be rejected, got {:?}", other),
+    }
+
+    //only a leading wildcard needs the reversed columns
+    let page = db.search_wildcard("調*", LeadingWildcard::Reject, 10, 0).unwrap();
+    assert_eq!(page.terms.len(), 1);
+}
+
+#[test]
+fn leading_wildcard_scans_unsupported_dictionaries() {
+    let (_database, db) = import_both("leading_scan");
+
+    let page = db.search_wildcard("*べる", LeadingWildcard::Scan, 10, 0).unwrap();
+    let expressions: Vec<&str> = page.terms.iter().map(|term| term.term.term.as_str()).collect();
+    assert_eq!(expressions, ["食べる", "調べる"]);
+    assert!(!page.has_more);
+}
@@ -1,27 +1,45 @@
-use rusqlite::{Connection, Result};
-use std::fs;
-use std::io;
+use yomi_dict_db::YomitanDatabase;
 
 fn main() {
     std::process::exit(real_main());
 }
 
 fn real_main() -> i32 {
-    let fname = std::path::Path::new("C:\\code\\yomidb\\testzip\\jitendex.zip");
-    let file = fs::File::open(fname).unwrap();
+    let fname = std::env::args().nth(1).unwrap_or(String::from("C:\\code\\yomidb\\testzip\\jitendex.zip"));
 
-    let mut archive = zip::ZipArchive::new(file).unwrap();
-    let indexResult = archive.by_name("index.json");
+    let db = match YomitanDatabase::open_database("yomidb.db") {
+        Ok(db) => db,
+        Err(err) => {
+            println!("could not open database: {}", err);
+            return 1;
+        }
+    };
 
-    if indexResult.is_ok()
-    {
-        println!("index.json found");
-    }
-    else {
-        println!("index.json not found");
-    }
+    let result = db.import_archive_with_progress(&fname, |progress| {
+        if let Some(file) = &progress.current_file {
+            println!(
+                "[{:?}] {}/{} banks, {} rows, {}/{} bytes: {}",
+                progress.phase,
+                progress.banks_done,
+                progress.banks_total,
+                progress.rows_inserted,
+                progress.bytes_read,
+                progress.bytes_total,
+                file
+            );
+        }
+    });
 
-    0
+    match result {
+        Ok(summary) => {
+            println!("imported {} ({} terms)", summary.title, summary.terms);
+            0
+        }
+        Err(err) => {
+            println!("import failed: {}", err);
+            1
+        }
+    }
 }
 
 /*fn main() -> Result<()> {